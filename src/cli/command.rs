@@ -0,0 +1,526 @@
+use std::path::PathBuf;
+
+use launch_service::Edit;
+
+use crate::cli::error::Error;
+
+/// Description of a subcommand, used for both parsing and `--help`
+struct Spec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    argument: Option<Argument>,
+    flags: &'static [Flag],
+    summary: &'static str,
+    /// Whether the host invokes the command as an action of the extension
+    action: bool,
+}
+
+/// Switch taken by a subcommand
+struct Flag {
+    name: &'static str,
+    summary: &'static str,
+    /// Whether the flag takes the place of the positional argument
+    replaces_argument: bool,
+}
+
+/// Positional arguments and flags given to a subcommand
+type Arguments = (Vec<String>, Vec<&'static Flag>);
+
+/// Positional argument taken by a subcommand
+enum Argument {
+    Required(&'static str),
+    Optional(&'static str),
+    /// Two required arguments, in order
+    Pair(&'static str, &'static str),
+}
+
+impl Spec {
+    /// Whether the command cannot run without its positional arguments
+    fn requires_argument(&self) -> bool {
+        matches!(
+            self.argument,
+            Some(Argument::Required(_)) | Some(Argument::Pair(..))
+        )
+    }
+}
+
+impl Argument {
+    fn usage(argument: &Option<Argument>) -> String {
+        match argument {
+            Some(Argument::Required(name)) => name.to_string(),
+            Some(Argument::Optional(name)) => format!("[{}]", name),
+            Some(Argument::Pair(first, second)) => format!("{} {}", first, second),
+            None => String::new(),
+        }
+    }
+}
+
+/// Flag of the commands rewriting a settings file
+const FORCE: Flag = Flag {
+    name: "--force",
+    summary: "Rewrite the settings file even if its comments or layout are lost",
+    replaces_argument: false,
+};
+
+const SPECS: &[Spec] = &[
+    Spec {
+        name: "query",
+        aliases: &["-q", "--query"],
+        argument: Some(Argument::Required("<query>")),
+        flags: &[Flag {
+            name: "--batch",
+            summary: "Read one query per line from stdin, tagging every match with its query",
+            replaces_argument: true,
+        }],
+        summary: "Print every service matching the query, one JSON record per line",
+        action: true,
+    },
+    Spec {
+        name: "execute",
+        aliases: &["-x", "--execute"],
+        argument: Some(Argument::Required("<id>")),
+        flags: &[],
+        summary: "Open the service with the given id",
+        action: true,
+    },
+    Spec {
+        name: "reveal",
+        aliases: &["-X", "--alter-execute"],
+        argument: Some(Argument::Required("<id>")),
+        flags: &[],
+        summary: "Reveal the service with the given id in Finder",
+        action: true,
+    },
+    Spec {
+        name: "serve",
+        aliases: &[],
+        argument: None,
+        flags: &[],
+        summary: "Answer line-delimited JSON requests from stdin until it is closed",
+        action: false,
+    },
+    Spec {
+        name: "listen",
+        aliases: &[],
+        argument: Some(Argument::Optional("<socket>")),
+        flags: &[],
+        summary: "Answer line-delimited JSON requests from clients of a Unix domain socket",
+        action: false,
+    },
+    Spec {
+        name: "check-config",
+        aliases: &[],
+        argument: None,
+        flags: &[],
+        summary: "Validate the merged settings, printing one JSON diagnostic per line",
+        action: false,
+    },
+    Spec {
+        name: "schema",
+        aliases: &[],
+        argument: None,
+        flags: &[],
+        summary: "Print the JSON Schema of every configurable option",
+        action: false,
+    },
+    Spec {
+        name: "describe",
+        aliases: &[],
+        argument: Some(Argument::Optional("<profile>")),
+        flags: &[],
+        summary: "Validate the extension profile, and print it as a JSON manifest",
+        action: false,
+    },
+    Spec {
+        name: "migrate",
+        aliases: &[],
+        argument: Some(Argument::Optional("<file>")),
+        flags: &[
+            Flag {
+                name: "--write",
+                summary: "Write the upgraded settings back to the file",
+                replaces_argument: false,
+            },
+            FORCE,
+        ],
+        summary: "Upgrade a settings file, by default the user one, printing every change as JSON",
+        action: false,
+    },
+    Spec {
+        name: "ignore",
+        aliases: &[],
+        argument: Some(Argument::Required("<path>")),
+        flags: &[FORCE],
+        summary: "Add the path to the ignored paths of the user settings",
+        action: false,
+    },
+    Spec {
+        name: "unignore",
+        aliases: &[],
+        argument: Some(Argument::Required("<path>")),
+        flags: &[FORCE],
+        summary: "Stop ignoring the path in the user settings",
+        action: false,
+    },
+    Spec {
+        name: "alias",
+        aliases: &[],
+        argument: Some(Argument::Pair("<id>", "<keyword>")),
+        flags: &[FORCE],
+        summary: "Add a search keyword to the service in the user settings",
+        action: false,
+    },
+];
+
+pub enum Command {
+    Help(Option<String>),
+    Version,
+    Query(String),
+    QueryBatch,
+    Execute(String),
+    Reveal(String),
+    Serve,
+    Listen(Option<PathBuf>),
+    CheckConfig,
+    Schema,
+    Describe(Option<PathBuf>),
+    /// Settings file to upgrade, whether to write it back,
+    /// and whether to rewrite a file losing its comments
+    Migrate(Option<PathBuf>, bool, bool),
+    /// Change to make to the user settings, and whether to rewrite a file losing its comments
+    Edit(Edit, bool),
+}
+
+impl Command {
+    /// Parse command from arguments, excluding the program name
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, Error> {
+        let mut args = args.into_iter();
+        let action = args
+            .next()
+            .ok_or_else(|| Error::usage("Command is missing, see --help"))?;
+        match action.trim() {
+            "-h" | "--help" | "help" => return Ok(Command::Help(args.next())),
+            "-V" | "--version" => return Ok(Command::Version),
+            _ => (),
+        }
+        let spec = find_spec(action.trim())
+            .ok_or_else(|| Error::usage(format!("Unexpected command: {}", action)))?;
+        let (positionals, flags) = match Self::arguments(spec, args)? {
+            Some(arguments) => arguments,
+            None => return Ok(Command::Help(Some(spec.name.to_owned()))),
+        };
+        let mut positionals = positionals.into_iter();
+        let replaced = flags.iter().any(|flag| flag.replaces_argument);
+        let mut required = |argument: &str| {
+            positionals
+                .next()
+                .ok_or_else(|| Error::usage(format!("{} is missing for {}", argument, spec.name)))
+        };
+        let mut second = None;
+        let content = match spec.argument {
+            Some(Argument::Required(argument)) if !replaced => Some(required(argument)?),
+            Some(Argument::Pair(first, other)) if !replaced => {
+                let first = required(first)?;
+                second = Some(required(other)?);
+                Some(first)
+            }
+            Some(Argument::Optional(_)) if !replaced => positionals.next(),
+            _ => None,
+        };
+        if let Some(extra) = positionals.next() {
+            return Err(Error::usage(format!("Unexpected argument: {}", extra)));
+        }
+        let has_flag = |name: &str| flags.iter().any(|flag| flag.name == name);
+        match (spec.name, content) {
+            ("query", None) if has_flag("--batch") => Ok(Command::QueryBatch),
+            ("query", Some(content)) => Ok(Command::Query(content)),
+            ("execute", Some(content)) => Ok(Command::Execute(content)),
+            ("reveal", Some(content)) => Ok(Command::Reveal(content)),
+            ("serve", None) => Ok(Command::Serve),
+            ("listen", socket) => Ok(Command::Listen(socket.map(PathBuf::from))),
+            ("check-config", None) => Ok(Command::CheckConfig),
+            ("schema", None) => Ok(Command::Schema),
+            ("describe", profile) => Ok(Command::Describe(profile.map(PathBuf::from))),
+            ("migrate", file) => Ok(Command::Migrate(
+                file.map(PathBuf::from),
+                has_flag("--write"),
+                has_flag("--force"),
+            )),
+            ("ignore", Some(path)) => Ok(Command::Edit(Edit::Ignore(path), has_flag("--force"))),
+            ("unignore", Some(path)) => {
+                Ok(Command::Edit(Edit::Unignore(path), has_flag("--force")))
+            }
+            ("alias", Some(id)) => Ok(Command::Edit(
+                Edit::Alias {
+                    id,
+                    keyword: second.unwrap_or_default(),
+                },
+                has_flag("--force"),
+            )),
+            _ => unreachable!("spec without command"),
+        }
+    }
+
+    /// Collect positional arguments and flags of the command, or `None` if help is requested
+    fn arguments<I: Iterator<Item = String>>(
+        spec: &'static Spec,
+        mut args: I,
+    ) -> Result<Option<Arguments>, Error> {
+        let mut positionals = Vec::new();
+        let mut flags = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--" => {
+                    positionals.extend(args);
+                    break;
+                }
+                name if name.starts_with('-') && name.len() > 1 => {
+                    match spec.flags.iter().find(|flag| flag.name == name) {
+                        Some(flag) => flags.push(flag),
+                        // Content starting with a dash, e.g. `-q -foo`
+                        None if spec.requires_argument() => positionals.push(arg),
+                        None => return Err(Error::usage(format!("Unexpected flag: {}", name))),
+                    }
+                }
+                _ => positionals.push(arg),
+            }
+        }
+        Ok(Some((positionals, flags)))
+    }
+}
+
+/// Names of the commands the host invokes, reported as the actions of the extension
+pub fn actions() -> Vec<String> {
+    SPECS
+        .iter()
+        .filter(|spec| spec.action)
+        .map(|spec| spec.name.to_owned())
+        .collect()
+}
+
+fn find_spec(name: &str) -> Option<&'static Spec> {
+    SPECS
+        .iter()
+        .find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// Help text for the whole program, or for a single command
+pub fn help(topic: Option<&str>) -> Result<String, Error> {
+    let program = env!("CARGO_PKG_NAME");
+    match topic {
+        None => {
+            let mut text = format!(
+                "Usage: {} [--settings <file>]... <command> [<args>]\n\nCommands:\n",
+                program
+            );
+            let usages = SPECS
+                .iter()
+                .map(|spec| {
+                    let usage = format!("{} {}", spec.name, Argument::usage(&spec.argument));
+                    usage.trim_end().to_owned()
+                })
+                .collect::<Vec<_>>();
+            let commands = usages
+                .iter()
+                .zip(SPECS)
+                .map(|(usage, spec)| (usage.as_str(), spec.summary))
+                .collect::<Vec<_>>();
+            text.push_str(&rows(&commands));
+            text.push_str("\nOptions:\n");
+            text.push_str(&rows(&[
+                (
+                    "--settings <file>",
+                    "Settings file, taking precedence over all other layers",
+                ),
+                ("-h, --help", "Print help"),
+                ("-V, --version", "Print version"),
+            ]));
+            Ok(text)
+        }
+        Some(topic) => {
+            let spec = find_spec(topic)
+                .ok_or_else(|| Error::usage(format!("Unexpected command: {}", topic)))?;
+            let mut text = format!(
+                "{}\n\nUsage: {} {} {}\n",
+                spec.summary,
+                program,
+                spec.name,
+                Argument::usage(&spec.argument)
+            );
+            if !spec.flags.is_empty() {
+                text.push_str("\nFlags:\n");
+                let flags = spec
+                    .flags
+                    .iter()
+                    .map(|flag| (flag.name, flag.summary))
+                    .collect::<Vec<_>>();
+                text.push_str(&rows(&flags));
+            }
+            if !spec.aliases.is_empty() {
+                text.push_str(&format!("\nAliases: {}\n", spec.aliases.join(", ")));
+            }
+            Ok(text)
+        }
+    }
+}
+
+/// Lines of a help section, the summaries lined up two spaces past the longest name
+fn rows(rows: &[(&str, &str)]) -> String {
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 2;
+    rows.iter()
+        .map(|(name, summary)| format!("    {:<width$}{}\n", name, summary, width = width))
+        .collect()
+}
+
+#[cfg(test)]
+mod command_test {
+    use launch_service::Edit;
+
+    use crate::cli::command::{actions, help, Command};
+    use crate::cli::error::ErrorKind;
+
+    fn parse(args: &[&str]) -> Result<Command, crate::cli::Error> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_subcommand() {
+        match parse(&["query", "saf"]) {
+            Ok(Command::Query(query)) => assert_eq!(query, "saf"),
+            _ => panic!("Failed to parse query"),
+        }
+    }
+
+    #[test]
+    fn test_parse_legacy_flags() {
+        assert!(matches!(parse(&["-q", "saf"]), Ok(Command::Query(_))));
+        assert!(matches!(
+            parse(&["-x", "/Applications/Safari.app"]),
+            Ok(Command::Execute(_))
+        ));
+        assert!(matches!(
+            parse(&["-X", "/Applications/Safari.app"]),
+            Ok(Command::Reveal(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_missing_content() {
+        let error = parse(&["-q"]).err().expect("Missing content is accepted");
+        assert_eq!(error.kind, ErrorKind::Usage);
+    }
+
+    #[test]
+    fn test_parse_unknown_command() {
+        let error = parse(&["--launch", "saf"])
+            .err()
+            .expect("Unknown command is accepted");
+        assert_eq!(error.kind, ErrorKind::Usage);
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert!(matches!(parse(&["--help"]), Ok(Command::Help(None))));
+        assert!(matches!(
+            parse(&["query", "--help"]),
+            Ok(Command::Help(Some(_)))
+        ));
+        assert!(help(Some("-q")).is_ok());
+        assert!(help(Some("launch")).is_err());
+    }
+
+    #[test]
+    fn test_help_columns() {
+        let text = help(None).expect("Failed to print help");
+        let describe = text
+            .lines()
+            .find(|line| line.trim_start().starts_with("describe"))
+            .expect("describe missed");
+        assert!(describe.contains("[<profile>]  Validate"));
+        let query = text
+            .lines()
+            .find(|line| line.trim_start().starts_with("query"))
+            .expect("query missed");
+        assert_eq!(query.find("Print"), describe.find("Validate"));
+    }
+
+    #[test]
+    fn test_parse_without_argument() {
+        assert!(matches!(parse(&["serve"]), Ok(Command::Serve)));
+        assert!(matches!(parse(&["schema"]), Ok(Command::Schema)));
+        assert!(parse(&["serve", "saf"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_optional_argument() {
+        assert!(matches!(parse(&["listen"]), Ok(Command::Listen(None))));
+        assert!(matches!(parse(&["describe"]), Ok(Command::Describe(None))));
+        assert!(matches!(
+            parse(&["listen", "/tmp/launch.sock"]),
+            Ok(Command::Listen(Some(_)))
+        ));
+    }
+
+    #[test]
+    fn test_parse_pair() {
+        match parse(&["alias", "com.microsoft.VSCode", "vsc"]) {
+            Ok(Command::Edit(Edit::Alias { id, keyword }, false)) => {
+                assert_eq!(id, "com.microsoft.VSCode");
+                assert_eq!(keyword, "vsc");
+            }
+            _ => panic!("Failed to parse alias"),
+        }
+        assert!(parse(&["alias", "com.microsoft.VSCode"]).is_err());
+        assert!(matches!(
+            parse(&["ignore", "/Applications/Chess.app"]),
+            Ok(Command::Edit(Edit::Ignore(_), false))
+        ));
+        assert!(matches!(
+            parse(&["unignore", "--force", "/Applications/Chess.app"]),
+            Ok(Command::Edit(Edit::Unignore(_), true))
+        ));
+    }
+
+    #[test]
+    fn test_parse_flag() {
+        assert!(matches!(
+            parse(&["query", "--batch"]),
+            Ok(Command::QueryBatch)
+        ));
+        assert!(matches!(parse(&["-q", "--batch"]), Ok(Command::QueryBatch)));
+        assert!(parse(&["query", "--batch", "saf"]).is_err());
+        assert!(parse(&["execute", "--batch", "saf"]).is_err());
+        assert!(matches!(
+            parse(&["migrate", "--write", "settings.yaml"]),
+            Ok(Command::Migrate(Some(_), true, false))
+        ));
+        assert!(matches!(
+            parse(&["migrate"]),
+            Ok(Command::Migrate(None, false, false))
+        ));
+    }
+
+    #[test]
+    fn test_actions() {
+        assert_eq!(actions(), vec!["query", "execute", "reveal"]);
+    }
+
+    #[test]
+    fn test_parse_dash_content() {
+        match parse(&["query", "--", "-saf"]) {
+            Ok(Command::Query(query)) => assert_eq!(query, "-saf"),
+            _ => panic!("Failed to parse query after separator"),
+        }
+        match parse(&["-q", "-foo"]) {
+            Ok(Command::Query(query)) => assert_eq!(query, "-foo"),
+            _ => panic!("Failed to parse dash query"),
+        }
+        assert!(parse(&["serve", "--foo"]).is_err());
+        match parse(&["alias", "com.example.App", "-x"]) {
+            Ok(Command::Edit(Edit::Alias { keyword, .. }, _)) => assert_eq!(keyword, "-x"),
+            _ => panic!("Failed to parse dash keyword"),
+        }
+        assert!(parse(&["migrate", "--wrte"]).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{self, Display, Formatter};
+use std::process;
+
+use serde::Serialize;
+
+/// Category of a failure, each mapped to a distinct exit code (following `sysexits.h`)
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    Usage,
+    Config,
+    Execution,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Usage => 64,
+            ErrorKind::Execution => 69,
+            ErrorKind::Config => 78,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Error {
+    #[serde(rename = "error")]
+    pub(crate) kind: ErrorKind,
+    code: i32,
+    message: String,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        Error {
+            kind,
+            code: kind.exit_code(),
+            message: message.into(),
+        }
+    }
+
+    pub fn usage<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Usage, message)
+    }
+
+    pub fn config<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Config, message)
+    }
+
+    pub fn execution<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Execution, message)
+    }
+
+    /// Single JSON line describing the error, meant for the host to parse
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.message.clone())
+    }
+
+    /// Report the error on stderr and terminate with its exit code
+    pub fn exit(&self) -> ! {
+        eprintln!("{}", self.to_line());
+        process::exit(self.code)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+    use std::collections::HashSet;
+
+    use crate::cli::error::{Error, ErrorKind};
+
+    #[test]
+    fn test_exit_codes_distinct() {
+        let codes: HashSet<i32> = vec![ErrorKind::Usage, ErrorKind::Config, ErrorKind::Execution]
+            .into_iter()
+            .map(ErrorKind::exit_code)
+            .collect();
+        assert_eq!(codes.len(), 3);
+        assert!(!codes.contains(&0));
+    }
+
+    #[test]
+    fn test_to_line() {
+        let error = Error::usage("Content is missing");
+        let expected = r#"{"error":"usage","code":64,"message":"Content is missing"}"#;
+        assert_eq!(error.to_line(), expected);
+    }
+}
@@ -0,0 +1,7 @@
+pub use command::{actions, help, Command};
+pub use error::Error;
+pub use options::Options;
+
+mod command;
+mod error;
+mod options;
@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use crate::cli::error::Error;
+
+/// Options given before the command, independent of it
+#[derive(Default)]
+pub struct Options {
+    /// Settings files given with `--settings`, in increasing precedence
+    pub settings: Vec<PathBuf>,
+}
+
+impl Options {
+    /// Take the global options leading the arguments, and return the remaining arguments,
+    /// starting with the command. Arguments of the command are left alone, e.g. a query
+    /// searching for `--settings`.
+    pub fn extract<I: IntoIterator<Item = String>>(args: I) -> Result<(Self, Vec<String>), Error> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--settings" {
+                let path = args
+                    .next()
+                    .ok_or_else(|| Error::usage("<file> is missing for --settings"))?;
+                options.settings.push(PathBuf::from(path));
+            } else if let Some(path) = arg.strip_prefix("--settings=") {
+                options.settings.push(PathBuf::from(path));
+            } else {
+                return Ok((options, std::iter::once(arg).chain(args).collect()));
+            }
+        }
+        Ok((options, Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod options_test {
+    use std::path::PathBuf;
+
+    use crate::cli::options::Options;
+
+    fn extract(args: &[&str]) -> (Options, Vec<String>) {
+        Options::extract(args.iter().map(|arg| arg.to_string())).expect("Failed to extract")
+    }
+
+    #[test]
+    fn test_extract_settings() {
+        let (options, remaining) =
+            extract(&["--settings", "a.json", "--settings=b.json", "query", "saf"]);
+        let expected = vec![PathBuf::from("a.json"), PathBuf::from("b.json")];
+        assert_eq!(options.settings, expected);
+        assert_eq!(remaining, vec!["query", "saf"]);
+    }
+
+    #[test]
+    fn test_extract_after_command() {
+        let (options, remaining) = extract(&["query", "--settings", "a.json"]);
+        assert!(options.settings.is_empty());
+        assert_eq!(remaining, vec!["query", "--settings", "a.json"]);
+        let (options, remaining) = extract(&["--settings=a.json", "query", "--", "--settings"]);
+        assert_eq!(options.settings, vec![PathBuf::from("a.json")]);
+        assert_eq!(remaining, vec!["query", "--", "--settings"]);
+    }
+
+    #[test]
+    fn test_extract_missing_file() {
+        assert!(Options::extract(vec!["--settings".to_owned()]).is_err());
+    }
+}
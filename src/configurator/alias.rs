@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::configurator::schema::Schema;
+use crate::utils;
+
+/// Extra search keywords and an optional title attached to a service
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Alias {
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Title shown instead of the one derived from the file name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl Schema for Alias {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "keywords": Vec::<String>::schema(),
+                "title": String::schema()
+            },
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Aliases split by what they are keyed by: a path, or a bundle identifier
+#[derive(Debug, Default)]
+pub struct Aliases {
+    by_path: HashMap<PathBuf, Alias>,
+    by_identifier: HashMap<String, Alias>,
+}
+
+impl Aliases {
+    /// Keys starting with `/` or `~` are paths, every other key is a bundle identifier
+    pub fn new(aliases: &HashMap<String, Alias>) -> Self {
+        let mut split = Aliases::default();
+        for (key, alias) in aliases {
+            if key.starts_with('/') || key.starts_with('~') {
+                split
+                    .by_path
+                    .insert(utils::expand_tilde(key), alias.clone());
+            } else {
+                split.by_identifier.insert(key.clone(), alias.clone());
+            }
+        }
+        split
+    }
+
+    pub fn by_path(&self, path: &Path) -> Option<&Alias> {
+        self.by_path.get(path)
+    }
+
+    pub fn by_identifier(&self, identifier: &str) -> Option<&Alias> {
+        self.by_identifier.get(identifier)
+    }
+
+    /// Whether any alias is keyed by a bundle identifier, which needs the bundle to be read
+    pub fn has_identifiers(&self) -> bool {
+        !self.by_identifier.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod alias_test {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::configurator::alias::{Alias, Aliases};
+
+    #[test]
+    fn test_deserialize() {
+        let alias: Alias = serde_json::from_value(json!({"keywords": ["ps"]})).expect("Failed");
+        assert_eq!(alias.keywords, vec!["ps"]);
+        assert_eq!(alias.title, None);
+    }
+
+    #[test]
+    fn test_split() {
+        let alias = Alias {
+            keywords: vec!["vsc".to_owned()],
+            title: Some("Code".to_owned()),
+        };
+        let mut aliases = HashMap::new();
+        aliases.insert("/Applications/Safari.app".to_owned(), Alias::default());
+        aliases.insert("com.microsoft.VSCode".to_owned(), alias.clone());
+        let aliases = Aliases::new(&aliases);
+        assert!(aliases
+            .by_path(Path::new("/Applications/Safari.app"))
+            .is_some());
+        assert_eq!(aliases.by_identifier("com.microsoft.VSCode"), Some(&alias));
+        assert!(aliases.by_identifier("/Applications/Safari.app").is_none());
+        assert!(aliases.has_identifiers());
+    }
+}
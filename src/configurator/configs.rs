@@ -1,130 +1,551 @@
-use std::collections::{HashMap, HashSet as Set};
+use std::collections::HashMap;
 use std::io::{self, Error, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 
+use crate::configurator::alias::{Alias, Aliases};
+use crate::configurator::diagnostics::{Diagnostic, Severity};
+use crate::configurator::locale;
+use crate::configurator::root::Root;
+use crate::configurator::schema::{self, Schema};
+use crate::query::{Filter, IgnoreRules, Kind};
 use crate::utils;
 use crate::utils::serde::deserialize_from_bytes;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Configs {
+    /// Version of the settings layout, see `migration`
+    #[serde(default = "initial_version")]
+    version: u64,
     internal: Internal,
+    #[serde(default)]
     configurable: Configurable,
+    #[serde(skip)]
+    ignore_rules: IgnoreRules,
+    #[serde(skip)]
+    roots: Vec<Root>,
+    #[serde(skip)]
+    aliases: Aliases,
+    #[serde(skip)]
+    warnings: Vec<Diagnostic>,
+    #[serde(skip)]
+    pref_names: HashMap<String, String>,
+    #[serde(skip)]
+    english_pref_names: HashMap<String, String>,
+    #[serde(skip)]
+    locales: Vec<String>,
+    /// Filters of the user followed by the internal ones
+    #[serde(skip)]
+    filters: Vec<Filter>,
 }
 
-#[derive(Deserialize)]
+fn initial_version() -> u64 {
+    1
+}
+
+#[derive(Deserialize, Serialize)]
 struct Internal {
-    paths: Vec<PathBuf>,
+    paths: Vec<Root>,
     #[serde(rename = "prefNames")]
-    preferred_names: HashMap<String, String>,
+    preferred_names: HashMap<String, PrefNames>,
+    /// Kind of every bundle extension listed
+    #[serde(rename = "bundleKinds", default = "Kind::defaults")]
+    bundle_kinds: HashMap<String, Kind>,
+    #[serde(default = "Filter::defaults")]
+    filters: Vec<Filter>,
+    /// Whether the XDG application folders are walked for `.desktop` files
+    #[serde(rename = "xdgApplications", default)]
+    xdg_applications: bool,
 }
 
-#[derive(Deserialize)]
-struct Configurable {
-    #[serde(rename = "ignorePaths")]
-    ignored_paths: ConfigurableValue,
+/// Entry of `prefNames`: the names of the panes in a locale, keyed by locale,
+/// or the English name of a pane, keyed by the pane
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum PrefNames {
+    Name(String),
+    Locale(HashMap<String, String>),
 }
 
-#[derive(Deserialize)]
-struct ConfigurableValue {
-    value: Set<PathBuf>,
+impl Schema for Internal {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "paths": Vec::<Root>::schema(),
+                "prefNames": HashMap::<String, PrefNames>::schema(),
+                "bundleKinds": HashMap::<String, Kind>::schema(),
+                "filters": Vec::<Filter>::schema(),
+                "xdgApplications": bool::schema()
+            },
+            "additionalProperties": false
+        })
+    }
 }
 
-macro_rules! expand_path {
-    ($paths: expr) => {
-        let expanded = $paths
-            .iter()
-            .filter_map(|path| Some(utils::expand_tilde(path.to_str()?)))
-            .collect();
-        *$paths = expanded;
+impl Schema for PrefNames {
+    fn schema() -> Value {
+        json!({"anyOf": [String::schema(), HashMap::<String, String>::schema()]})
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct ConfigurableValue<T> {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+    value: T,
+}
+
+/// Declare the options shown in the preferences UI.
+/// Each option is `field: Type = "key", "Display Name", default;`, documented by its doc comment,
+/// which becomes the description in the schema, and optionally followed by values suggested in the schema.
+macro_rules! configurable {
+    ($($(#[doc = $doc: literal])* $field: ident: $type: ty = $key: literal, $display_name: literal, $default: expr $(, $examples: expr)?;)*) => {
+        #[derive(Deserialize, Serialize)]
+        #[serde(default)]
+        struct Configurable {
+            $(
+                #[serde(rename = $key)]
+                $field: ConfigurableValue<$type>,
+            )*
+        }
+
+        impl Default for Configurable {
+            fn default() -> Self {
+                Configurable {
+                    $(
+                        $field: ConfigurableValue {
+                            display_name: Some($display_name.to_owned()),
+                            value: $default,
+                        },
+                    )*
+                }
+            }
+        }
+
+        impl Configurable {
+            /// Schema of every option, keyed by its name
+            fn properties() -> Map<String, Value> {
+                let defaults = Self::default();
+                let mut properties = Map::new();
+                $(
+                    let description = [$($doc.trim()),*].join(" ");
+                    let default = serde_json::to_value(&defaults.$field.value).unwrap_or_default();
+                    let examples: Option<&[&str]> = None $(.or(Some($examples)))?;
+                    properties.insert(
+                        $key.to_owned(),
+                        schema::option::<$type>($display_name, &description, default, examples),
+                    );
+                )*
+                properties
+            }
+        }
     };
 }
 
+configurable! {
+    /// Paths that are neither listed nor walked into. Folders ignore everything under them,
+    /// `*` and `**` match any name and any number of folders, and `!` re-includes what an
+    /// earlier pattern ignored
+    ignored_paths: Vec<String> = "ignorePaths", "Paths to Ignore", Vec::new();
+    /// Paths walked in addition to the built-in ones, e.g. `/opt/tools` or a team share
+    extra_paths: Vec<PathBuf> = "extraPaths", "Additional Paths", Vec::new();
+    /// Search keywords and titles of services, keyed by their path or bundle identifier,
+    /// e.g. `com.adobe.Photoshop: {keywords: [ps]}`
+    aliases: HashMap<String, Alias> = "aliases", "Aliases", HashMap::new();
+    /// Locale of the preference pane names, e.g. `fr_CA`, falling back to `fr` then `en`.
+    /// Taken from `LC_ALL`, `LC_MESSAGES` or `LANG` if empty
+    locale: String = "locale", "Language", String::new(), locale::LOCALES;
+    /// Filters applied to every path walked before the built-in ones, e.g.
+    /// `{rule: name, pattern: Uninstaller, action: prune}`
+    filters: Vec<Filter> = "filters", "Filters", Vec::new();
+}
+
+/// Warning for a setting skipped because of the error
+fn skipped<E: ToString>(key: String, error: E) -> Diagnostic {
+    let message = format!("{}, and the setting is skipped", error.to_string());
+    Diagnostic::new(Severity::Warning, key, message)
+}
+
 impl Configs {
-    /// Construct config from given yaml file
+    /// Construct config from given yaml or json content
     pub fn from<S: AsRef<str>>(content: S) -> io::Result<Self> {
-        let mut configs: Configs = deserialize_from_bytes(content.as_ref().as_bytes())
+        Self::from_value(deserialize_from_bytes(content.as_ref().as_bytes())?)
+    }
+
+    /// Construct config from an already parsed document
+    pub fn from_value(value: Value) -> io::Result<Self> {
+        let mut configs: Configs = serde_json::from_value(value)
             .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
-        expand_path!(&mut configs.internal.paths);
-        expand_path!(&mut configs.configurable.ignored_paths.value);
+        configs.expand_roots();
+        let patterns = configs
+            .configurable
+            .ignored_paths
+            .value
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| {
+                utils::expand_vars(pattern).map_err(|error| {
+                    let key = format!("configurable.ignorePaths.value[{}]", index);
+                    let diagnostic = Diagnostic::new(Severity::Error, key, error.to_string());
+                    Error::new(ErrorKind::InvalidData, diagnostic)
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        configs.ignore_rules = IgnoreRules::new(&patterns);
+        let mut aliases = HashMap::new();
+        for (key, alias) in &configs.configurable.aliases.value {
+            match utils::expand_vars(key) {
+                Ok(expanded) => {
+                    aliases.insert(expanded, alias.clone());
+                }
+                Err(error) => configs.warnings.push(skipped(
+                    format!("configurable.aliases.value.{}", key),
+                    error,
+                )),
+            }
+        }
+        configs.aliases = Aliases::new(&aliases);
+        configs.locales = locale::chain(&configs.configurable.locale.value);
+        configs.pref_names = configs.resolve_pref_names(&configs.locales);
+        configs.english_pref_names = configs.resolve_pref_names(&[locale::FALLBACK.to_owned()]);
+        configs.filters = configs.configurable.filters.value.clone();
+        configs
+            .filters
+            .extend(configs.internal.filters.iter().cloned());
+        if configs.internal.xdg_applications {
+            // The XDG folders are walked for desktop entries, whatever `bundleKinds` lists
+            configs
+                .internal
+                .bundle_kinds
+                .entry("desktop".to_owned())
+                .or_insert(Kind::DesktopEntry);
+        }
         Ok(configs)
     }
 
-    /// Get ignored path
-    pub fn get_ignore_paths(&self) -> &Set<PathBuf> {
+    /// Names of the panes, looked up in each locale of the chain in turn.
+    /// Names keyed by the pane belong to the fallback locale.
+    fn resolve_pref_names(&self, chain: &[String]) -> HashMap<String, String> {
+        let entries = &self.internal.preferred_names;
+        let mut names = HashMap::new();
+        for locale in chain.iter().rev() {
+            if let Some(PrefNames::Locale(table)) = entries.get(locale) {
+                names.extend(table.clone());
+            }
+            if locale == locale::FALLBACK {
+                names.extend(entries.iter().filter_map(|(pane, entry)| match entry {
+                    PrefNames::Name(name) => Some((pane.to_owned(), name.to_owned())),
+                    PrefNames::Locale(_) => None,
+                }));
+            }
+        }
+        names
+    }
+
+    /// Expand the internal and extra roots, skipping roots with undefined variables
+    fn expand_roots(&mut self) {
+        let internal = self
+            .internal
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(index, root)| (format!("internal.paths[{}]", index), root.clone()));
+        let xdg = if self.internal.xdg_applications {
+            utils::application_dirs()
+        } else {
+            Vec::new()
+        };
+        let xdg = xdg.into_iter().filter(|dir| dir.is_dir()).map(|dir| {
+            let mut root = Root::from(dir);
+            // Desktop files are often linked from elsewhere, e.g. exported by Flatpak
+            root.follow_symlinks = true;
+            root.extensions = Some(vec!["desktop".to_owned()]);
+            ("internal.xdgApplications".to_owned(), root)
+        });
+        let extra = self
+            .configurable
+            .extra_paths
+            .value
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let key = format!("configurable.extraPaths.value[{}]", index);
+                (key, Root::from(path.clone()))
+            });
+        let mut roots: Vec<Root> = Vec::new();
+        for (key, root) in internal.chain(xdg).chain(extra) {
+            match root.expanded() {
+                Ok(root) if roots.iter().all(|other| other.path != root.path) => roots.push(root),
+                Ok(_) => (),
+                Err(error) => self.warnings.push(skipped(key, error)),
+            }
+        }
+        roots.sort_by_key(|root| std::cmp::Reverse(root.priority));
+        self.roots = roots;
+    }
+
+    /// Get problems found while constructing the configs, which did not prevent it
+    pub fn get_warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// JSON Schema of the configurable section, describing every option of the preferences UI
+    pub fn schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "Configurable settings",
+            "type": "object",
+            "properties": Configurable::properties(),
+            "additionalProperties": false
+        })
+    }
+
+    /// JSON Schema of the whole settings document, which keys are checked against
+    pub(crate) fn document_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "version": u64::schema(),
+                "internal": Internal::schema(),
+                "configurable": Self::schema()
+            },
+            "additionalProperties": false
+        })
+    }
+
+    /// Get ignore patterns, in the order they are applied
+    pub fn get_ignore_paths(&self) -> &Vec<String> {
         &self.configurable.ignored_paths.value
     }
 
-    /// Get paths need to be cached
-    pub fn get_paths(&self) -> &Vec<PathBuf> {
-        &self.internal.paths
+    /// Get aliases of services
+    pub fn get_aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    /// Get ignore rules compiled from the ignore patterns
+    pub fn get_ignore_rules(&self) -> &IgnoreRules {
+        &self.ignore_rules
     }
 
+    /// Get roots need to be cached, including the extra paths, highest priority first
+    pub fn get_roots(&self) -> &Vec<Root> {
+        &self.roots
+    }
+
+    /// Get paths need to be cached, including the extra paths, highest priority first
+    pub fn get_paths(&self) -> Vec<&Path> {
+        self.roots.iter().map(|root| root.path.as_path()).collect()
+    }
+
+    /// Get names of the preference panes in the selected locale
     pub fn get_pref_names(&self) -> &HashMap<String, String> {
-        &self.internal.preferred_names
+        &self.pref_names
+    }
+
+    /// Panes named in `prefNames`, along with the key of the setting naming them
+    pub(crate) fn get_named_panes(&self) -> Vec<(String, &str)> {
+        let mut panes = Vec::new();
+        for (key, entry) in &self.internal.preferred_names {
+            match entry {
+                PrefNames::Name(_) => {
+                    panes.push((format!("internal.prefNames.{}", key), key.as_str()))
+                }
+                PrefNames::Locale(table) => panes.extend(table.keys().map(|pane| {
+                    (
+                        format!("internal.prefNames.{}.{}", key, pane),
+                        pane.as_str(),
+                    )
+                })),
+            }
+        }
+        panes.sort();
+        panes
+    }
+
+    /// Get English names of the preference panes
+    pub fn get_english_pref_names(&self) -> &HashMap<String, String> {
+        &self.english_pref_names
+    }
+
+    /// Get kinds of the bundles listed, keyed by extension
+    pub fn get_bundle_kinds(&self) -> &HashMap<String, Kind> {
+        &self.internal.bundle_kinds
+    }
+
+    /// Get locales to look localized names up in, most specific first
+    pub fn get_locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// Get the filters every walked path goes through, in order
+    pub fn get_filters(&self) -> &[Filter] {
+        &self.filters
     }
 }
 
 #[cfg(test)]
 pub mod configs_test {
+    use std::path::Path;
+
+    use serde_json::{json, Value};
+
     use crate::configurator::configs::Configs;
+    use crate::configurator::diagnostics::Diagnostic;
+    use crate::query::{Condition, Kind};
+    use crate::utils::application_dirs;
+    use crate::utils::serde::deserialize_from_bytes;
+
+    /// Number of XDG application folders present, each walked as a root
+    pub fn xdg_roots() -> usize {
+        application_dirs().iter().filter(|dir| dir.is_dir()).count()
+    }
 
     pub fn get_content() -> String {
         r#"
 {
+  "version": 2,
   "configurable": {
     "ignorePaths": {
       "displayName": "Paths to Ignore",
       "value": []
+    },
+    "extraPaths": {
+      "displayName": "Additional Paths",
+      "value": []
+    },
+    "aliases": {
+      "displayName": "Aliases",
+      "value": {}
+    },
+    "locale": {
+      "displayName": "Language",
+      "value": ""
+    },
+    "filters": {
+      "displayName": "Filters",
+      "value": []
     }
   },
   "internal": {
     "paths": [
       "/System/Library/CoreServices/Finder.app",
-      "/System/Library/CoreServices/Applications",
+      {"path": "/System/Library/CoreServices/Applications", "depth": 1},
       "/System/Library/PreferencePanes",
       "/System/Applications",
       "~/Applications", 
       "/Applications"
     ],
     "prefNames": {
-      "SoftwareUpdate": "Software Update",
-      "iCloudPref": "iCloud",
-      "Accounts": "Users & Groups",
-      "AppStore": "App Store",
-      "Appearance": "General",
-      "DateAndTime": "Date & Time",
-      "DesktopScreenEffectsPref": "Desktop & Screen Saver",
-      "DigiHubDiscs": "CDs & DVDs",
-      "EnergySaver": "Energy Saver",
-      "Expose": "Mission Control",
-      "FibreChannel": "Fibre Channel",
-      "InternetAccounts": "Internet Accounts",
-      "Localization": "Language & Region",
-      "ParentalControls": "Parental Controls",
-      "PrintAndFax": "Printers & Fax",
-      "PrintAndScan": "Printers & Scanners",
-      "Security": "Security & Privacy",
-      "SharingPref": "Sharing",
-      "Speech": "Siri",
-      "StartupDisk": "Startup Disk",
-      "TimeMachine": "Time Machine",
-      "TouchID": "Touch ID",
-      "UniversalAccessPref": "Accessibility",
-      "Wallet": "Wallet & Apple Pay",
-      "AppleIDPrefPane": "Apple ID",
-      "FamilySharingPrefPane": "Family Sharing"
-    }
+      "en": {
+        "SoftwareUpdate": "Software Update",
+        "iCloudPref": "iCloud",
+        "Accounts": "Users & Groups",
+        "AppStore": "App Store",
+        "Appearance": "General",
+        "DateAndTime": "Date & Time",
+        "DesktopScreenEffectsPref": "Desktop & Screen Saver",
+        "DigiHubDiscs": "CDs & DVDs",
+        "EnergySaver": "Energy Saver",
+        "Expose": "Mission Control",
+        "FibreChannel": "Fibre Channel",
+        "InternetAccounts": "Internet Accounts",
+        "Localization": "Language & Region",
+        "ParentalControls": "Parental Controls",
+        "PrintAndFax": "Printers & Fax",
+        "PrintAndScan": "Printers & Scanners",
+        "Security": "Security & Privacy",
+        "SharingPref": "Sharing",
+        "Speech": "Siri",
+        "StartupDisk": "Startup Disk",
+        "TimeMachine": "Time Machine",
+        "TouchID": "Touch ID",
+        "UniversalAccessPref": "Accessibility",
+        "Wallet": "Wallet & Apple Pay",
+        "AppleIDPrefPane": "Apple ID",
+        "FamilySharingPrefPane": "Family Sharing"
+      }
+    },
+    "bundleKinds": {
+      "app": "application",
+      "prefPane": "preferencePane"
+    },
+    "filters": [
+      {"rule": "hidden"},
+      {"rule": "symlink"},
+      {"rule": "ignore"},
+      {"rule": "bundle"}
+    ],
+    "xdgApplications": true
   }
 }"#
         .to_owned()
     }
 
+    pub fn get_configs() -> Configs {
+        Configs::from(get_content()).expect("Failed to parse test settings")
+    }
+
     #[test]
     fn test_new() {
         let res = Configs::from(get_content());
-        assert!(res.is_ok(), res.err().unwrap().to_string());
+        assert!(res.is_ok(), "{}", res.err().unwrap());
+    }
+
+    #[test]
+    fn test_shipped_settings() {
+        let shipped: Value = deserialize_from_bytes(include_str!("../../settings.yaml").as_bytes())
+            .expect("Failed to parse settings.yaml");
+        let content: Value =
+            deserialize_from_bytes(get_content().as_bytes()).expect("Failed to parse content");
+        assert_eq!(shipped, content);
+        assert!(Configs::from(include_str!("../../settings.yaml")).is_ok());
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = Configs::schema();
+        let ignore_paths = &schema["properties"]["ignorePaths"];
+        assert_eq!(ignore_paths["title"], json!("Paths to Ignore"));
+        let description = ignore_paths["description"].as_str().unwrap_or_default();
+        assert!(description.starts_with("Paths that are neither listed nor walked into."));
+        assert_eq!(ignore_paths["properties"]["value"]["default"], json!([]));
+        assert!(ignore_paths["properties"]["value"].get("enum").is_none());
+        let locale = &schema["properties"]["locale"]["properties"]["value"];
+        // Any locale is accepted, e.g. `de_CH.UTF-8`, those offered are only suggested
+        assert!(locale.get("enum").is_none());
+        let locales = &locale["examples"];
+        assert_eq!(locales[0], json!(""));
+        assert!(locales
+            .as_array()
+            .is_some_and(|locales| locales.contains(&json!("fr_CA"))));
+    }
+
+    #[test]
+    fn test_shipped_configurable() {
+        let shipped: Value = deserialize_from_bytes(include_str!("../../settings.yaml").as_bytes())
+            .expect("Failed to parse settings.yaml");
+        let properties = Configs::schema()["properties"].clone();
+        let properties = properties.as_object().expect("Properties missing");
+        let configurable = shipped["configurable"]
+            .as_object()
+            .expect("Section missing");
+        assert_eq!(configurable.len(), properties.len());
+        for (key, option) in configurable {
+            let property = &properties[key];
+            assert_eq!(option["displayName"], property["title"]);
+            assert_eq!(option["value"], property["properties"]["value"]["default"]);
+        }
+    }
+
+    #[test]
+    fn test_configurable_default() {
+        let configs = Configs::from(r#"{"internal": {"paths": [], "prefNames": {}}}"#);
+        let configs = configs.expect("Failed to fill in configurable defaults");
+        assert!(configs.get_ignore_paths().is_empty());
     }
 
     #[test]
@@ -138,7 +559,130 @@ pub mod configs_test {
     fn test_get_system_paths() {
         let res = Configs::from(get_content()).unwrap();
         let cached_path = res.get_paths();
-        assert_eq!(cached_path.len(), 6);
+        assert_eq!(cached_path.len(), 6 + xdg_roots());
+    }
+
+    #[test]
+    fn test_get_xdg_roots() {
+        let res = Configs::from(get_content()).unwrap();
+        for root in &res.get_roots()[6..] {
+            assert!(root.path.ends_with("applications"));
+            assert_eq!(root.extensions, Some(vec!["desktop".to_owned()]));
+            assert!(root.follow_symlinks);
+        }
+        let mut value: Value = deserialize_from_bytes(get_content().as_bytes()).unwrap();
+        value["internal"]["xdgApplications"] = json!(false);
+        let res = Configs::from_value(value).unwrap();
+        assert_eq!(res.get_paths().len(), 6);
+    }
+
+    #[test]
+    fn test_get_roots() {
+        let res = Configs::from(get_content()).unwrap();
+        let roots = res.get_roots();
+        assert_eq!(roots[1].depth, Some(1));
+        assert_eq!(roots[4].depth, None);
+        assert!(roots[4].path.is_absolute());
+    }
+
+    #[test]
+    fn test_get_roots_extra_paths() {
+        let res = Configs::from(
+            r#"{
+                "configurable": {"extraPaths": {"value": ["/opt/tools", "/Applications", "~/Shared", "/opt/tools"]}},
+                "internal": {"paths": ["/Applications"], "prefNames": {}}
+            }"#,
+        )
+        .unwrap();
+        let paths = res.get_paths();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(
+            paths[..2],
+            [Path::new("/Applications"), Path::new("/opt/tools")]
+        );
+        assert!(paths[2].is_absolute());
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let res = Configs::from(
+            r#"{
+                "configurable": {"extraPaths": {"value": ["$HOME/Tools", "$CONFIGS_UNSET/Applications"]}},
+                "internal": {"paths": ["${CONFIGS_UNSET:-/opt}/Applications"], "prefNames": {}}
+            }"#,
+        )
+        .unwrap();
+        let home = std::env::var("HOME").unwrap_or_default();
+        let tools = format!("{}/Tools", home);
+        let paths = res.get_paths();
+        assert_eq!(
+            paths,
+            vec![Path::new("/opt/Applications"), Path::new(&tools)]
+        );
+        let warnings = res.get_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].key, "configurable.extraPaths.value[1]");
+    }
+
+    #[test]
+    fn test_expand_variables_ignore_paths() {
+        let error = Configs::from(
+            r#"{
+                "configurable": {"ignorePaths": {"value": ["/a", "!$CONFIGS_UNSET/b"]}},
+                "internal": {"paths": [], "prefNames": {}}
+            }"#,
+        )
+        .err()
+        .expect("Undefined variable accepted");
+        let diagnostic = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<Diagnostic>())
+            .expect("Key missed");
+        assert_eq!(diagnostic.key, "configurable.ignorePaths.value[1]");
+        assert!(diagnostic.message.starts_with("$CONFIGS_UNSET"));
+    }
+
+    #[test]
+    fn test_get_roots_by_priority() {
+        let res = Configs::from(
+            r#"{"internal": {"paths": ["/a", {"path": "/b", "priority": 1}, "/c"], "prefNames": {}}}"#,
+        )
+        .unwrap();
+        let paths = res.get_paths();
+        assert_eq!(
+            paths,
+            vec![Path::new("/b"), Path::new("/a"), Path::new("/c")]
+        );
+    }
+
+    #[test]
+    fn test_get_filters() {
+        let res = Configs::from(get_content()).unwrap();
+        assert_eq!(res.get_filters().len(), 4);
+        let res = Configs::from(
+            r#"{
+                "configurable": {"filters": {"value": [{"rule": "name", "pattern": "Uninstaller"}]}},
+                "internal": {"paths": [], "prefNames": {}}
+            }"#,
+        )
+        .unwrap();
+        let filters = res.get_filters();
+        assert_eq!(filters.len(), 5);
+        assert!(matches!(filters[0].condition, Condition::Name { .. }));
+        assert!(matches!(filters[4].condition, Condition::Bundle));
+    }
+
+    #[test]
+    fn test_get_bundle_kinds() {
+        let res = Configs::from(get_content()).unwrap();
+        let mut expected = Kind::defaults();
+        expected.insert("desktop".to_owned(), Kind::DesktopEntry);
+        assert_eq!(res.get_bundle_kinds(), &expected);
+        let res = Configs::from(
+            r#"{"internal": {"paths": [], "prefNames": {}, "bundleKinds": {"app": "application"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(res.get_bundle_kinds().len(), 1);
     }
 
     #[test]
@@ -147,4 +691,26 @@ pub mod configs_test {
         let pref_names = res.get_pref_names();
         assert_eq!(pref_names.len(), 26);
     }
+
+    #[test]
+    fn test_get_pref_names_locale() {
+        let res = Configs::from(
+            r#"{
+                "configurable": {"locale": {"value": "de_CH.UTF-8"}},
+                "internal": {"paths": [], "prefNames": {
+                    "en": {"Localization": "Language & Region", "Speech": "Siri", "Network": "Network"},
+                    "de": {"Localization": "Sprache & Region", "Speech": "Siri"},
+                    "de_CH": {"Speech": "Siri (CH)"},
+                    "Network": "Network Settings"
+                }}
+            }"#,
+        )
+        .unwrap();
+        let name = |pane: &str| res.get_pref_names().get(pane).map(String::as_str);
+        assert_eq!(name("Speech"), Some("Siri (CH)"));
+        assert_eq!(name("Localization"), Some("Sprache & Region"));
+        assert_eq!(name("Network"), Some("Network Settings"));
+        let english = res.get_english_pref_names();
+        assert_eq!(english.get("Speech").map(String::as_str), Some("Siri"));
+    }
 }
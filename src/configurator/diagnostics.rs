@@ -0,0 +1,602 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::configurator::configs::Configs;
+use crate::configurator::loader;
+use crate::query::{Catalog, Rule};
+use crate::utils;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A problem found in the settings, pointing at the offending key
+#[derive(Serialize, Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Path of the offending key, e.g. `internal.paths[2]`
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new<K: Into<String>, M: Into<String>>(severity: Severity, key: K, message: M) -> Self {
+        Diagnostic {
+            severity,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Validate the merged settings document, reporting every problem found
+pub fn diagnose(document: &Value) -> Vec<Diagnostic> {
+    match validate(document) {
+        Ok((configs, mut diagnostics)) => {
+            let catalog = Catalog::new(&configs);
+            check_pref_names(&configs, &catalog, &mut diagnostics);
+            check_desktop_entries(&catalog, &mut diagnostics);
+            diagnostics
+        }
+        Err(diagnostics) => diagnostics,
+    }
+}
+
+/// Validate the merged settings document without walking the paths.
+/// The configs are constructed unless an error is found.
+pub fn validate(document: &Value) -> Result<(Configs, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let configs = match Configs::from_value(document.clone()) {
+        Ok(configs) => configs,
+        Err(error) => {
+            // Errors of a single setting carry its key
+            let diagnostic = error
+                .get_ref()
+                .and_then(|error| error.downcast_ref::<Diagnostic>())
+                .cloned()
+                .unwrap_or_else(|| Diagnostic::new(Severity::Error, "", error.to_string()));
+            return Err(vec![diagnostic]);
+        }
+    };
+    let mut diagnostics = configs.get_warnings().to_vec();
+    unknown_keys(document, &Configs::document_schema(), "", &mut diagnostics);
+    let mut roots = paths_of(document, "/internal/paths", "internal.paths");
+    roots.extend(paths_of(
+        document,
+        "/configurable/extraPaths/value",
+        "configurable.extraPaths.value",
+    ));
+    // The built-in roots cover every platform, so those missing on this one are expected
+    let built_in = loader::defaults()
+        .map(|defaults| paths_of(&defaults, "/internal/paths", "internal.paths"))
+        .unwrap_or_default();
+    check_roots(&roots, &built_in, &mut diagnostics);
+    check_extensions(document, &configs, &mut diagnostics);
+    check_filters(document, "/configurable/filters/value", &mut diagnostics);
+    check_filters(document, "/internal/filters", &mut diagnostics);
+    let ignored = patterns_of(
+        document,
+        "/configurable/ignorePaths/value",
+        "configurable.ignorePaths.value",
+    );
+    check_ignore_paths(&ignored, &roots, &mut diagnostics);
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        Err(diagnostics)
+    } else {
+        Ok((configs, diagnostics))
+    }
+}
+
+/// Report keys of the document that the schema has no property for
+fn unknown_keys(document: &Value, schema: &Value, key: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(alternatives) = schema.get("anyOf").and_then(Value::as_array) {
+        // The alternative of the same JSON type as the value
+        if let Some(schema) = alternatives
+            .iter()
+            .find(|schema| has_type(document, schema))
+        {
+            unknown_keys(document, schema, key, diagnostics);
+        }
+        return;
+    }
+    match document {
+        Value::Object(document) => {
+            for (name, value) in document {
+                let path = if key.is_empty() {
+                    name.to_owned()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                let property = schema
+                    .get("properties")
+                    .and_then(|properties| properties.get(name))
+                    .or_else(|| schema.get("additionalProperties"));
+                match property {
+                    Some(Value::Bool(false)) | None => diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        path,
+                        "Unknown key is ignored",
+                    )),
+                    Some(property) => unknown_keys(value, property, &path, diagnostics),
+                }
+            }
+        }
+        Value::Array(document) => {
+            if let Some(items) = schema.get("items") {
+                for (index, value) in document.iter().enumerate() {
+                    unknown_keys(value, items, &format!("{}[{}]", key, index), diagnostics);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Whether the value is of the JSON type of the schema
+fn has_type(value: &Value, schema: &Value) -> bool {
+    matches!(
+        (value, schema["type"].as_str()),
+        (Value::Object(_), Some("object"))
+            | (Value::Array(_), Some("array"))
+            | (Value::String(_), Some("string"))
+            | (Value::Bool(_), Some("boolean"))
+            | (Value::Number(_), Some("integer") | Some("number"))
+    )
+}
+
+/// Expanded paths listed in the array at `pointer`, with their key paths.
+/// Entries are either paths, or objects holding a `path`.
+fn paths_of(document: &Value, pointer: &str, key: &str) -> Vec<(String, PathBuf)> {
+    document
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .map(|paths| {
+            paths
+                .iter()
+                .enumerate()
+                .filter_map(|(index, path)| match path {
+                    Value::String(path) => Some((format!("{}[{}]", key, index), path.as_str())),
+                    Value::Object(root) => Some((
+                        format!("{}[{}].path", key, index),
+                        root.get("path")?.as_str()?,
+                    )),
+                    _ => None,
+                })
+                .filter_map(|(key, path)| Some((key, utils::expand_path(path).ok()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ignore patterns listed in the array at `pointer`, with their key paths
+fn patterns_of(document: &Value, pointer: &str, key: &str) -> Vec<(String, String, Rule)> {
+    document
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .map(|patterns| {
+            patterns
+                .iter()
+                .enumerate()
+                .filter_map(|(index, pattern)| {
+                    let pattern = pattern.as_str()?;
+                    let key = format!("{}[{}]", key, index);
+                    let rule = Rule::parse(&utils::expand_vars(pattern).ok()?);
+                    Some((key, pattern.to_owned(), rule))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check the roots exist and are walked once, leaving alone missing roots of `built_in`
+fn check_roots(
+    roots: &[(String, PathBuf)],
+    built_in: &[(String, PathBuf)],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (index, (key, root)) in roots.iter().enumerate() {
+        let is_built_in = built_in
+            .iter()
+            .any(|(built_in_key, built_in)| built_in_key == key && built_in == root);
+        if !root.exists() {
+            if is_built_in {
+                continue;
+            }
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                key.as_str(),
+                format!("{} does not exist", root.display()),
+            ));
+        } else if root.is_dir() && read_dir(root).is_err() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                key.as_str(),
+                format!("{} is not readable", root.display()),
+            ));
+        }
+        let duplicate = roots[..index].iter().find(|(_, other)| other == root);
+        let parent = roots
+            .iter()
+            .find(|(_, other)| other != root && root.starts_with(other));
+        if let Some((duplicate_key, duplicate)) = duplicate {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                key.as_str(),
+                format!(
+                    "{} duplicates {}, and is skipped",
+                    duplicate.display(),
+                    duplicate_key
+                ),
+            ));
+        } else if let Some((parent_key, parent)) = parent {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                key.as_str(),
+                format!(
+                    "{} is inside {} ({}), and is walked twice",
+                    root.display(),
+                    parent.display(),
+                    parent_key
+                ),
+            ));
+        }
+    }
+}
+
+fn check_extensions(document: &Value, configs: &Configs, diagnostics: &mut Vec<Diagnostic>) {
+    let roots = match document
+        .pointer("/internal/paths")
+        .and_then(Value::as_array)
+    {
+        Some(roots) => roots,
+        None => return,
+    };
+    for (index, root) in roots.iter().enumerate() {
+        let extensions = match root.get("extensions").and_then(Value::as_array) {
+            Some(extensions) => extensions,
+            None => continue,
+        };
+        for (position, extension) in extensions.iter().enumerate() {
+            let extension = extension.as_str().unwrap_or_default();
+            if !configs.get_bundle_kinds().contains_key(extension) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    format!("internal.paths[{}].extensions[{}]", index, position),
+                    format!(
+                        "{} has no kind in internal.bundleKinds, and is never listed",
+                        extension
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Report filters including paths without a `kind`, which only include bundles of known kinds
+fn check_filters(document: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let filters = match document.pointer(pointer).and_then(Value::as_array) {
+        Some(filters) => filters,
+        None => return,
+    };
+    let key = pointer.trim_start_matches('/').replace('/', ".");
+    for (index, filter) in filters.iter().enumerate() {
+        let includes = filter["action"] == "include";
+        if includes && filter["rule"] != "bundle" && filter.get("kind").is_none() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                format!("{}[{}].kind", key, index),
+                "Kind is missing, so only paths with an extension of internal.bundleKinds are included",
+            ));
+        }
+    }
+}
+
+fn check_ignore_paths(
+    ignored: &[(String, String, Rule)],
+    roots: &[(String, PathBuf)],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, pattern, rule) in ignored {
+        let base = match rule.base() {
+            Some(base) => base,
+            None => continue,
+        };
+        let overlaps = |root: &PathBuf| base.starts_with(root) || root.starts_with(&base);
+        if !roots.iter().any(|(_, root)| overlaps(root)) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                key.as_str(),
+                format!("{} is not under any path, and has no effect", pattern),
+            ));
+        }
+    }
+}
+
+/// Report panes named in `prefNames` but not found, leaving alone those of the built-in defaults
+fn check_pref_names(configs: &Configs, catalog: &Catalog, diagnostics: &mut Vec<Diagnostic>) {
+    let built_in: HashSet<String> = loader::defaults()
+        .and_then(Configs::from_value)
+        .map(|defaults| {
+            defaults
+                .get_named_panes()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect()
+        })
+        .unwrap_or_default();
+    let panes: HashSet<&OsStr> = catalog
+        .services()
+        .iter()
+        .map(|service| service.id())
+        .filter(|path| path.extension() == Some(OsStr::new("prefPane")))
+        .filter_map(Path::file_stem)
+        .collect();
+    for (key, name) in configs.get_named_panes() {
+        if !panes.contains(OsStr::new(name)) && !built_in.contains(&key) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Info,
+                key,
+                format!("No {}.prefPane is found", name),
+            ));
+        }
+    }
+}
+
+/// Explain why each desktop entry walked is not listed
+fn check_desktop_entries(catalog: &Catalog, diagnostics: &mut Vec<Diagnostic>) {
+    for (path, exclusion) in catalog.excluded() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Info,
+            "internal.xdgApplications",
+            format!("{} is not listed, as {}", path.display(), exclusion),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod diagnostics_test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use serde_json::{json, Value};
+
+    use crate::configurator::diagnostics::{diagnose, validate, Diagnostic, Severity};
+    use crate::configurator::loader::Loader;
+
+    fn root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("diagnostics-{}-{}", std::process::id(), name));
+        fs::create_dir_all(root.join("Network.prefPane")).expect("Failed to create");
+        root
+    }
+
+    fn document(paths: Value, ignored: Value, pref_names: Value) -> Value {
+        json!({
+            "configurable": {"ignorePaths": {"displayName": "Paths to Ignore", "value": ignored}},
+            "internal": {"paths": paths, "prefNames": pref_names}
+        })
+    }
+
+    fn find<'a>(diagnostics: &'a [Diagnostic], key: &str) -> Option<&'a Diagnostic> {
+        diagnostics.iter().find(|diagnostic| diagnostic.key == key)
+    }
+
+    #[test]
+    fn test_diagnose_clean() {
+        let root = root("clean");
+        let document = document(
+            json!([root]),
+            json!([root.join("a")]),
+            json!({"Network": "Net"}),
+        );
+        assert!(diagnose(&document).is_empty());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_unknown_keys() {
+        let mut document = document(json!([]), json!([]), json!({}));
+        document["configurable"]["ignorePath"] = json!({"value": []});
+        let diagnostics = diagnose(&document);
+        let unknown = find(&diagnostics, "configurable.ignorePath").expect("Unknown key missed");
+        assert_eq!(unknown.severity, Severity::Warning);
+        assert!(find(&diagnostics, "configurable.ignorePaths.displayName").is_none());
+    }
+
+    #[test]
+    fn test_diagnose_known_keys_with_defaults() {
+        let mut document = document(
+            json!(["/Applications", {"path": "/opt", "followSymlinks": false}]),
+            json!([]),
+            json!({"de": {"Speech": "Siri"}, "Network": "Net"}),
+        );
+        document["internal"]["filters"] = json!([{"rule": "hidden", "invert": false}]);
+        document["internal"]["bundleKinds"] = json!({"app": "application"});
+        document["configurable"]["aliases"] = json!({"value": {"/a": {"keywords": []}}});
+        let diagnostics = validate(&document).expect("Failed to validate").1;
+        let unknown = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message == "Unknown key is ignored");
+        assert!(unknown.is_none(), "{:?}", unknown);
+        document["internal"]["filters"][0]["inverted"] = json!(true);
+        document["internal"]["paths"][1]["depht"] = json!(1);
+        let diagnostics = validate(&document).expect("Failed to validate").1;
+        assert!(find(&diagnostics, "internal.filters[0].inverted").is_some());
+        assert!(find(&diagnostics, "internal.paths[1].depht").is_some());
+    }
+
+    #[test]
+    fn test_diagnose_roots() {
+        let root = root("roots");
+        let nested = root.join("Network.prefPane");
+        let missing = root.join("missing");
+        let document = document(
+            json!([root, nested, {"path": missing, "depth": 1}, root]),
+            json!([]),
+            json!({}),
+        );
+        let diagnostics = diagnose(&document);
+        assert!(find(&diagnostics, "internal.paths[0]").is_none());
+        assert!(find(&diagnostics, "internal.paths[1]").is_some());
+        assert!(find(&diagnostics, "internal.paths[2].path").is_some());
+        let duplicate = find(&diagnostics, "internal.paths[3]").expect("Duplicate missed");
+        assert!(duplicate
+            .message
+            .ends_with("duplicates internal.paths[0], and is skipped"));
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_built_in_roots() {
+        let defaults = Loader::new().merged().expect("Failed to load");
+        let diagnostics = validate(&defaults).expect("Failed to validate").1;
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| !diagnostic.message.ends_with("does not exist")));
+        // Listed by the user, the same root is expected to exist
+        let mut document = defaults;
+        document["configurable"]["extraPaths"]["value"] = json!(["/System/Library/Missing"]);
+        document["internal"]["paths"][0] = json!("/System/Library/Missing");
+        let diagnostics = validate(&document).expect("Failed to validate").1;
+        assert!(find(&diagnostics, "internal.paths[0]").is_some());
+        assert!(find(&diagnostics, "configurable.extraPaths.value[0]").is_some());
+    }
+
+    #[test]
+    fn test_diagnose_filters() {
+        let mut document = document(json!([]), json!([]), json!({}));
+        document["configurable"]["filters"] = json!({"value": [
+            {"rule": "name", "pattern": "^Go", "action": "include"},
+            {"rule": "executable", "action": "include", "kind": "tool", "invert": false}
+        ]});
+        let diagnostics = validate(&document).expect("Failed to validate").1;
+        let missing =
+            find(&diagnostics, "configurable.filters.value[0].kind").expect("Missing kind missed");
+        assert_eq!(missing.severity, Severity::Warning);
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_diagnose_extensions() {
+        let root = root("extensions");
+        let document = document(
+            json!([{"path": root, "extensions": ["app", "bundle"]}]),
+            json!([]),
+            json!({}),
+        );
+        let diagnostics = diagnose(&document);
+        assert!(find(&diagnostics, "internal.paths[0].extensions[0]").is_none());
+        assert!(find(&diagnostics, "internal.paths[0].extensions[1]").is_some());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_extra_paths() {
+        let root = root("extra");
+        let mut document = document(json!([]), json!([]), json!({}));
+        document["configurable"]["extraPaths"] = json!({"value": [root, root.join("missing")]});
+        let diagnostics = diagnose(&document);
+        assert!(find(&diagnostics, "configurable.extraPaths.value[0]").is_none());
+        assert!(find(&diagnostics, "configurable.extraPaths.value[1]").is_some());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_ignore_paths() {
+        let root = root("ignore");
+        let document = document(
+            json!([root]),
+            json!([root.join("a"), "/elsewhere", "**/*.app", "/"]),
+            json!({}),
+        );
+        let diagnostics = diagnose(&document);
+        assert!(find(&diagnostics, "configurable.ignorePaths.value[0]").is_none());
+        assert!(find(&diagnostics, "configurable.ignorePaths.value[1]").is_some());
+        assert!(find(&diagnostics, "configurable.ignorePaths.value[2]").is_none());
+        assert!(find(&diagnostics, "configurable.ignorePaths.value[3]").is_none());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_pref_names() {
+        let root = root("pref");
+        let document = document(
+            json!([root]),
+            json!([]),
+            json!({"Network": "N", "Speech": "S"}),
+        );
+        let diagnostics = diagnose(&document);
+        assert!(find(&diagnostics, "internal.prefNames.Network").is_none());
+        let missing = find(&diagnostics, "internal.prefNames.Speech").expect("Missing pane missed");
+        assert_eq!(missing.severity, Severity::Info);
+        let mut built_in = Loader::new().merged().expect("Failed to load");
+        built_in["internal"]["paths"] = json!([root]);
+        let diagnostics = diagnose(&built_in);
+        assert!(find(&diagnostics, "internal.prefNames.en.Speech").is_none());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_desktop_entries() {
+        let root = root("desktop");
+        let entry = "[Desktop Entry]\nType=Application\nName=Helper\nNoDisplay=true\n";
+        fs::write(root.join("helper.desktop"), entry).expect("Failed to write");
+        let mut document = document(json!([root]), json!([]), json!({}));
+        document["internal"]["bundleKinds"] = json!({"desktop": "desktopEntry"});
+        let diagnostics = diagnose(&document);
+        let excluded = find(&diagnostics, "internal.xdgApplications").expect("Exclusion missed");
+        assert_eq!(excluded.severity, Severity::Info);
+        assert!(excluded.message.contains("helper.desktop"));
+        assert!(excluded.message.ends_with("NoDisplay is true"));
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_undefined_variable() {
+        let root = root("undefined");
+        let paths = document(
+            json!([root, "$DIAGNOSTICS_UNSET/Applications"]),
+            json!([]),
+            json!({}),
+        );
+        let diagnostics = diagnose(&paths);
+        let undefined = find(&diagnostics, "internal.paths[1]").expect("Undefined variable missed");
+        assert_eq!(undefined.severity, Severity::Warning);
+        assert!(undefined.message.contains("$DIAGNOSTICS_UNSET"));
+        let ignored = document(json!([root]), json!(["${DIAGNOSTICS_UNSET}/a"]), json!({}));
+        let diagnostics = diagnose(&ignored);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].key, "configurable.ignorePaths.value[0]");
+        assert!(diagnostics[0].message.starts_with("$DIAGNOSTICS_UNSET"));
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_diagnose_invalid() {
+        let document = json!({"internal": {"paths": 1}});
+        let diagnostics = diagnose(&document);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+}
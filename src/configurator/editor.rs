@@ -0,0 +1,296 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::configurator::configs::Configs;
+use crate::configurator::loader::{merge_layer, Loader};
+use crate::configurator::migration::{migrate, VERSION};
+use crate::query::IgnoreRules;
+use crate::utils;
+use crate::utils::serde::{deserialize_from_bytes, ensure_round_trip, serialize_as, Format};
+
+/// Change made to a settings layer on behalf of the user
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Ignore the path, or any other `ignorePaths` pattern
+    Ignore(String),
+    /// Stop ignoring the path: its pattern is removed, or else negated
+    Unignore(String),
+    /// Add a search keyword to the service with the path or bundle identifier
+    Alias { id: String, keyword: String },
+}
+
+impl Edit {
+    /// Apply the edit to the layer, merged onto `base`, the layers below.
+    /// The layer holds only its own ignore patterns, appended to those of `base`,
+    /// while keywords missing from the layer start from `base`, as a list replaces theirs.
+    /// Returns whether anything is changed.
+    pub fn apply(&self, layer: &mut Value, base: &Value) -> bool {
+        match self {
+            Edit::Ignore(pattern) => {
+                let patterns = list(layer, &IGNORE_PATHS, json!([]));
+                let negated = Value::from(format!("!{}", pattern));
+                let pattern = Value::from(pattern.as_str());
+                if patterns.last() == Some(&pattern) {
+                    return false;
+                }
+                // Moving the pattern to the end lets it win over the negations before it
+                patterns.retain(|existing| existing != &pattern && existing != &negated);
+                patterns.push(pattern);
+                true
+            }
+            Edit::Unignore(pattern) => {
+                let below = base
+                    .pointer(&pointer(&IGNORE_PATHS))
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                let patterns = list(layer, &IGNORE_PATHS, json!([]));
+                let length = patterns.len();
+                patterns.retain(|existing| existing.as_str() != Some(pattern));
+                let removed = patterns.len() < length;
+                let remaining = below
+                    .iter()
+                    .chain(patterns.iter())
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>();
+                if !IgnoreRules::new(&remaining).is_ignored(Path::new(pattern)) {
+                    return removed;
+                }
+                // Still ignored by a broader pattern, which the negation overrides
+                patterns.push(Value::from(format!("!{}", pattern)));
+                true
+            }
+            Edit::Alias { id, keyword } => {
+                let keys = ["configurable", "aliases", "value", id, "keywords"];
+                let initial = base
+                    .pointer(&pointer(&keys))
+                    .filter(|value| value.is_array())
+                    .cloned()
+                    .unwrap_or_else(|| json!([]));
+                let keywords = list(layer, &keys, initial);
+                let keyword = Value::from(keyword.as_str());
+                if keywords.contains(&keyword) {
+                    return false;
+                }
+                keywords.push(keyword);
+                true
+            }
+        }
+    }
+}
+
+/// Apply the edit to the settings file, one of the layers of the loader,
+/// created if it does not exist yet.
+/// The file is upgraded to the current version, and the edited settings are merged
+/// with every other layer and checked to construct configs before the file is replaced.
+/// A file whose comments or layout would be lost in rewriting it is left alone unless `force`.
+/// Returns whether the file is changed.
+pub fn edit_file<P: AsRef<Path>>(
+    path: P,
+    loader: &Loader,
+    edit: &Edit,
+    force: bool,
+) -> io::Result<bool> {
+    let path = path.as_ref();
+    let (base, above) = loader.split(path)?;
+    let (format, mut layer) = match fs::read(path) {
+        Ok(content) if content.iter().all(u8::is_ascii_whitespace) => {
+            (Format::detect(&content), json!({}))
+        }
+        Ok(content) => {
+            let format = Format::detect(&content);
+            let layer = deserialize_from_bytes(&content)?;
+            if !force {
+                ensure_round_trip(format, &content, &layer)?;
+            }
+            (format, layer)
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let format = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => Format::Json,
+                _ => Format::Yaml,
+            };
+            (format, json!({ "version": VERSION }))
+        }
+        Err(error) => return Err(error),
+    };
+    if !layer.is_object() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is not a mapping", path.display()),
+        ));
+    }
+    let migrated = !migrate(&mut layer)?.is_empty();
+    if !edit.apply(&mut layer, &base) && !migrated {
+        return Ok(false);
+    }
+    let mut merged = base;
+    merge_layer(&mut merged, layer.clone());
+    for layer in above {
+        merge_layer(&mut merged, layer);
+    }
+    Configs::from_value(merged)?;
+    utils::write_atomically(path, &serialize_as(format, &layer)?)?;
+    Ok(true)
+}
+
+/// Keys of the ignore patterns
+const IGNORE_PATHS: [&str; 3] = ["configurable", "ignorePaths", "value"];
+
+/// JSON pointer to the keys
+fn pointer(keys: &[&str]) -> String {
+    keys.iter()
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// List at the keys of the layer, created along with the objects holding it if needed,
+/// and starting from `initial`
+fn list<'a>(layer: &'a mut Value, keys: &[&str], initial: Value) -> &'a mut Vec<Value> {
+    let mut value = layer;
+    for key in keys {
+        if !value.is_object() {
+            *value = Value::Object(Map::new());
+        }
+        value = value
+            .as_object_mut()
+            .expect("Value is just made an object")
+            .entry(key.to_string())
+            .or_insert(Value::Null);
+    }
+    if !value.is_array() {
+        *value = initial;
+    }
+    value.as_array_mut().expect("Value is just made an array")
+}
+
+#[cfg(test)]
+mod editor_test {
+    use std::fs;
+    use std::path::Path;
+
+    use serde_json::{json, Value};
+
+    use crate::configurator::editor::{edit_file, Edit};
+    use crate::configurator::loader::Loader;
+
+    fn base() -> Value {
+        json!({
+            "configurable": {"ignorePaths": {"value": ["/opt"]}},
+            "internal": {"paths": ["/Applications"], "prefNames": {}}
+        })
+    }
+
+    /// Loader with the base below the file
+    fn loader(path: &Path) -> Loader {
+        Loader::new()
+            .content(base().to_string())
+            .optional_file(path)
+    }
+
+    #[test]
+    fn test_ignore() {
+        let mut layer = json!({"configurable": {"locale": {"value": "de"}}});
+        assert!(Edit::Ignore("/Applications/Chess.app".to_owned()).apply(&mut layer, &base()));
+        assert!(!Edit::Ignore("/Applications/Chess.app".to_owned()).apply(&mut layer, &base()));
+        let expected = json!({"configurable": {
+            "locale": {"value": "de"},
+            "ignorePaths": {"value": ["/Applications/Chess.app"]}
+        }});
+        assert_eq!(layer, expected);
+    }
+
+    #[test]
+    fn test_unignore() {
+        let mut layer = json!({"configurable": {"ignorePaths": {"value": ["/a", "/b"]}}});
+        assert!(Edit::Unignore("/a".to_owned()).apply(&mut layer, &base()));
+        assert!(!Edit::Unignore("/a".to_owned()).apply(&mut layer, &base()));
+        assert!(Edit::Unignore("/b/c".to_owned()).apply(&mut layer, &base()));
+        assert!(!Edit::Unignore("/b/c".to_owned()).apply(&mut layer, &base()));
+        let patterns = &layer["configurable"]["ignorePaths"]["value"];
+        assert_eq!(patterns, &json!(["/b", "!/b/c"]));
+        assert!(Edit::Ignore("/b/c".to_owned()).apply(&mut layer, &base()));
+        let patterns = &layer["configurable"]["ignorePaths"]["value"];
+        assert_eq!(patterns, &json!(["/b", "/b/c"]));
+        // Patterns of the layers below are negated, as they cannot be removed from here
+        assert!(Edit::Unignore("/opt/tools".to_owned()).apply(&mut layer, &base()));
+        let patterns = &layer["configurable"]["ignorePaths"]["value"];
+        assert_eq!(patterns, &json!(["/b", "/b/c", "!/opt/tools"]));
+    }
+
+    #[test]
+    fn test_alias() {
+        let mut layer = json!({});
+        let edit = Edit::Alias {
+            id: "com.microsoft.VSCode".to_owned(),
+            keyword: "vsc".to_owned(),
+        };
+        assert!(edit.apply(&mut layer, &base()));
+        assert!(!edit.apply(&mut layer, &base()));
+        let expected = json!({"configurable": {"aliases": {"value": {
+            "com.microsoft.VSCode": {"keywords": ["vsc"]}
+        }}}});
+        assert_eq!(layer, expected);
+    }
+
+    #[test]
+    fn test_edit_file() {
+        let folder = std::env::temp_dir().join(format!("editor-{}", std::process::id()));
+        let path = folder.join("settings.json");
+        let edit = Edit::Ignore("/Applications/Chess.app".to_owned());
+        assert!(edit_file(&path, &loader(&path), &edit, false).expect("Failed to create"));
+        assert!(!edit_file(&path, &loader(&path), &edit, false).expect("Failed to edit"));
+        let content = fs::read_to_string(&path).expect("Failed to read");
+        let layer: Value = serde_json::from_str(&content).expect("Failed to parse");
+        assert_eq!(layer["version"], json!(2));
+        assert_eq!(
+            layer["configurable"]["ignorePaths"]["value"],
+            json!(["/Applications/Chess.app"])
+        );
+        fs::remove_dir_all(folder).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_edit_file_comments() {
+        let path =
+            std::env::temp_dir().join(format!("editor-comments-{}.yaml", std::process::id()));
+        let content = "# Mine\nversion: 2\nconfigurable:\n  locale: {value: de}  # Swiss\n";
+        fs::write(&path, content).expect("Failed to write");
+        let edit = Edit::Ignore("/Applications/Chess.app".to_owned());
+        let error = edit_file(&path, &loader(&path), &edit, false).expect_err("Comments dropped");
+        assert!(error.to_string().contains("--force"));
+        assert_eq!(fs::read_to_string(&path).expect("Failed to read"), content);
+        assert!(edit_file(&path, &loader(&path), &edit, true).expect("Failed to force"));
+        let rewritten = fs::read_to_string(&path).expect("Failed to read");
+        assert!(!rewritten.contains("# Mine"));
+        let edit = Edit::Ignore("/Applications/Go.app".to_owned());
+        assert!(edit_file(&path, &loader(&path), &edit, false).expect("Failed to edit rewritten"));
+        fs::remove_file(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_edit_file_invalid() {
+        let path = std::env::temp_dir().join(format!("editor-invalid-{}.yaml", std::process::id()));
+        fs::write(&path, "internal:\n  paths: 1\n").expect("Failed to write");
+        let edit = Edit::Ignore("/a".to_owned());
+        assert!(edit_file(&path, &loader(&path), &edit, true).is_err());
+        let content = fs::read_to_string(&path).expect("Failed to read");
+        assert_eq!(content, "internal:\n  paths: 1\n");
+        fs::remove_file(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_edit_file_layers_above() {
+        let path = std::env::temp_dir().join(format!("editor-above-{}.yaml", std::process::id()));
+        let edit = Edit::Ignore("/a".to_owned());
+        // The edited file is valid on its own, but not with the layer above it
+        let invalid = loader(&path)
+            .content(r#"{"configurable": {"ignorePaths": {"value": ["$EDITOR_UNSET"]}}}"#);
+        assert!(edit_file(&path, &invalid, &edit, false).is_err());
+        assert!(!path.exists());
+        assert!(edit_file(&path, &Loader::new(), &edit, false).is_err());
+    }
+}
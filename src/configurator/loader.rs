@@ -0,0 +1,377 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::configurator::configs::Configs;
+use crate::configurator::migration::migrate;
+use crate::utils;
+use crate::utils::serde::{deserialize_from_bytes, merge};
+
+/// Built-in settings, the lowest layer every other layer is merged onto
+const DEFAULTS: &str = include_str!("../../settings.yaml");
+
+/// Names of the settings file looked up in the system and user directories
+const FILE_NAMES: &[&str] = &["settings.yaml", "settings.yml", "settings.json"];
+
+/// Patterns that every layer adds to, instead of replacing them
+const IGNORE_PATHS: &str = "/configurable/ignorePaths/value";
+
+/// Environment variable holding a whole settings document
+pub const SETTINGS_VAR: &str = "SETTINGS";
+
+enum Source {
+    File { path: PathBuf, required: bool },
+    Env(String),
+    Content(String),
+}
+
+impl Source {
+    fn describe(&self) -> String {
+        match self {
+            Source::File { path, .. } => path.display().to_string(),
+            Source::Env(name) => name.to_owned(),
+            Source::Content(_) => "settings".to_owned(),
+        }
+    }
+}
+
+/// Layered settings: each source is merged onto the previous ones, key by key
+pub struct Loader {
+    sources: Vec<Source>,
+}
+
+impl Loader {
+    /// Loader with only the built-in defaults
+    pub fn new() -> Self {
+        Loader {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Loader with the standard layers, in increasing precedence:
+    /// built-in defaults, system file, user file, then the `SETTINGS` environment variable
+    pub fn standard() -> Self {
+        Self::system()
+            .optional_file(find_file(&user_dir()))
+            .env(SETTINGS_VAR)
+    }
+
+    /// Loader with the layers below the user file: built-in defaults and system file
+    pub fn system() -> Self {
+        Self::new().optional_file(find_file(&system_dir()))
+    }
+
+    /// Settings file of the user, whether it exists or not
+    pub fn user_file() -> PathBuf {
+        find_file(&user_dir())
+    }
+
+    /// Add a settings file that must exist
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Source::File {
+            path: path.into(),
+            required: true,
+        });
+        self
+    }
+
+    /// Add a settings file that is skipped if it does not exist
+    pub fn optional_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Source::File {
+            path: path.into(),
+            required: false,
+        });
+        self
+    }
+
+    /// Add an environment variable holding a settings document, skipped if it is not set
+    pub fn env<S: Into<String>>(mut self, name: S) -> Self {
+        self.sources.push(Source::Env(name.into()));
+        self
+    }
+
+    /// Add a settings document
+    pub fn content<S: Into<String>>(mut self, content: S) -> Self {
+        self.sources.push(Source::Content(content.into()));
+        self
+    }
+
+    /// Settings files of every layer
+    pub fn files(&self) -> Vec<&Path> {
+        self.sources
+            .iter()
+            .filter_map(|source| match source {
+                Source::File { path, .. } => Some(path.as_path()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Merge every layer and construct the configs
+    pub fn load(&self) -> io::Result<Configs> {
+        Configs::from_value(self.merged()?)
+    }
+
+    /// Merge every layer into a single document
+    pub fn merged(&self) -> io::Result<Value> {
+        let mut merged = defaults()?;
+        for source in &self.sources {
+            if let Some(layer) = source.layer()? {
+                merge_layer(&mut merged, layer);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Split the layers around the settings file: the layers below it merged into a document,
+    /// and those above it in increasing precedence, to merge onto the file once it is edited
+    pub(crate) fn split(&self, path: &Path) -> io::Result<(Value, Vec<Value>)> {
+        let position = self
+            .sources
+            .iter()
+            .position(|source| matches!(source, Source::File { path: file, .. } if file == path))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} is not a settings layer", path.display()),
+                )
+            })?;
+        let mut below = defaults()?;
+        for source in &self.sources[..position] {
+            if let Some(layer) = source.layer()? {
+                merge_layer(&mut below, layer);
+            }
+        }
+        let mut above = Vec::new();
+        for source in &self.sources[position + 1..] {
+            above.extend(source.layer()?);
+        }
+        Ok((below, above))
+    }
+}
+
+impl Source {
+    /// Document of the layer, upgraded to the current version,
+    /// or `None` if the layer is empty or an optional one is missing
+    fn layer(&self) -> io::Result<Option<Value>> {
+        let mut layer = match self {
+            Source::File { path, required } => match fs::read_to_string(path) {
+                Ok(content) => parse(&self.describe(), &content)?,
+                Err(ref error) if error.kind() == ErrorKind::NotFound && !required => {
+                    return Ok(None)
+                }
+                Err(error) => {
+                    return Err(Error::new(
+                        error.kind(),
+                        format!("Failed to read {}: {}", path.display(), error),
+                    ))
+                }
+            },
+            Source::Env(name) => match std::env::var(name) {
+                Ok(content) => parse(&self.describe(), &content)?,
+                Err(_) => return Ok(None),
+            },
+            Source::Content(content) => parse(&self.describe(), content)?,
+        };
+        if layer.is_null() {
+            return Ok(None);
+        }
+        migrate(&mut layer).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("Failed to migrate {}: {}", self.describe(), error),
+            )
+        })?;
+        Ok(Some(layer))
+    }
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merge the layer onto the layers below, key by key.
+/// The `ignorePaths` of the layer are appended to theirs, so patterns of every layer apply.
+pub(crate) fn merge_layer(merged: &mut Value, mut layer: Value) {
+    let below = merged.pointer(IGNORE_PATHS).and_then(Value::as_array);
+    if let (Some(below), Some(Value::Array(patterns))) = (below, layer.pointer_mut(IGNORE_PATHS)) {
+        let mut all = below.clone();
+        all.append(patterns);
+        *patterns = all;
+    }
+    merge(merged, layer);
+}
+
+/// Document of the built-in defaults
+pub(crate) fn defaults() -> io::Result<Value> {
+    parse("built-in defaults", DEFAULTS)
+}
+
+fn parse(source: &str, content: &str) -> io::Result<Value> {
+    if content.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    deserialize_from_bytes(content.as_bytes()).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to parse {}: {}", source, error),
+        )
+    })
+}
+
+/// Directory of the system-wide settings file
+fn system_dir() -> PathBuf {
+    Path::new("/etc").join(env!("CARGO_PKG_NAME"))
+}
+
+/// Directory of the user settings file, under `XDG_CONFIG_HOME`
+fn user_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| utils::expand_tilde("~/.config"))
+        .join(env!("CARGO_PKG_NAME"))
+}
+
+/// The first settings file found in the directory, or the preferred name if there is none yet
+fn find_file(dir: &Path) -> PathBuf {
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join(FILE_NAMES[0]))
+}
+
+#[cfg(test)]
+mod loader_test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::configurator::configs::configs_test::xdg_roots;
+    use crate::configurator::loader::Loader;
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("loader-{}-{}", std::process::id(), name));
+        fs::write(&path, content).expect("Failed to write");
+        path
+    }
+
+    #[test]
+    fn test_load_defaults() {
+        let configs = Loader::new().load().expect("Failed to load");
+        assert_eq!(configs.get_paths().len(), 6 + xdg_roots());
+        assert!(configs.get_ignore_paths().is_empty());
+    }
+
+    #[test]
+    fn test_load_precedence() {
+        let file = write_file(
+            "precedence.yaml",
+            "internal:\n  paths: [/Applications]\n  prefNames:\n    Speech: Voice\n",
+        );
+        let configs = Loader::new()
+            .file(&file)
+            .content(r#"{"internal": {"prefNames": {"Custom": "Custom Pane"}}}"#)
+            .content(r#"{"internal": {"paths": ["/opt/apps"]}}"#)
+            .load()
+            .expect("Failed to load");
+        assert_eq!(configs.get_paths()[0], Path::new("/opt/apps"));
+        assert_eq!(configs.get_paths().len(), 1 + xdg_roots());
+        let pref_names = configs.get_pref_names();
+        assert_eq!(pref_names.len(), 27);
+        assert_eq!(pref_names.get("Speech").map(String::as_str), Some("Voice"));
+        fs::remove_file(file).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_load_ignore_paths() {
+        let ignored = |patterns: &str| {
+            format!(
+                r#"{{"configurable": {{"ignorePaths": {{"value": {}}}}}}}"#,
+                patterns
+            )
+        };
+        let configs = Loader::new()
+            .content(ignored(r#"["/opt", "/srv"]"#))
+            .content(r#"{"configurable": {"locale": {"value": "de"}}}"#)
+            .content(ignored(r#"["!/opt/tools"]"#))
+            .load()
+            .expect("Failed to load");
+        assert_eq!(configs.get_ignore_paths(), &["/opt", "/srv", "!/opt/tools"]);
+    }
+
+    #[test]
+    fn test_load_migrated() {
+        let file = write_file(
+            "migrated.yaml",
+            "internal:\n  prefNames:\n    Speech: Voice\n",
+        );
+        let merged = Loader::new().file(&file).merged().expect("Failed to load");
+        assert_eq!(merged["internal"]["prefNames"]["en"]["Speech"], "Voice");
+        assert!(merged["internal"]["prefNames"].get("Speech").is_none());
+        let future = Loader::new().content(r#"{"version": 100}"#).load();
+        assert!(future.is_err());
+        fs::remove_file(file).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_files() {
+        let loader = Loader::new()
+            .file("/etc/a.yaml")
+            .env("SETTINGS")
+            .optional_file("/etc/b.yaml");
+        let expected = vec![Path::new("/etc/a.yaml"), Path::new("/etc/b.yaml")];
+        assert_eq!(loader.files(), expected);
+    }
+
+    #[test]
+    fn test_split() {
+        let file = write_file("split.yaml", "configurable:\n  locale: {value: de}\n");
+        let loader = Loader::new()
+            .content(r#"{"configurable": {"ignorePaths": {"value": ["/opt"]}}}"#)
+            .file(&file)
+            .content(r#"{"configurable": {"locale": {"value": "fr"}}}"#);
+        let (below, above) = loader.split(&file).expect("Failed to split");
+        assert_eq!(below["configurable"]["ignorePaths"]["value"][0], "/opt");
+        assert_eq!(below["configurable"]["locale"]["value"], "");
+        assert_eq!(above.len(), 1);
+        assert_eq!(above[0]["configurable"]["locale"]["value"], "fr");
+        assert!(loader.split(Path::new("/etc/other.yaml")).is_err());
+        fs::remove_file(file).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_load_missing_files() {
+        let missing = std::env::temp_dir().join("loader-missing.json");
+        assert!(Loader::new().optional_file(&missing).load().is_ok());
+        assert!(Loader::new().file(&missing).load().is_err());
+    }
+
+    #[test]
+    fn test_load_empty_file() {
+        let file = write_file("empty.yaml", "\n");
+        let configs = Loader::new().file(&file).load().expect("Failed to load");
+        assert_eq!(configs.get_paths().len(), 6 + xdg_roots());
+        fs::remove_file(file).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_load_unset_env() {
+        let configs = Loader::new().env("LOADER_TEST_UNSET_SETTINGS").load();
+        assert!(configs.is_ok());
+    }
+
+    #[test]
+    fn test_load_invalid_layer() {
+        let error = Loader::new()
+            .content("{")
+            .load()
+            .err()
+            .expect("Invalid layer accepted");
+        assert!(error.to_string().starts_with("Failed to parse settings"));
+    }
+}
@@ -0,0 +1,94 @@
+/// Locale used when none is set, whose names are also matched in every other locale
+pub const FALLBACK: &str = "en";
+
+/// Locales suggested for `configurable.locale`, the empty one standing for the environment.
+/// Any other locale is accepted, e.g. `de_CH` or `de_CH.UTF-8`.
+pub const LOCALES: &[&str] = &[
+    "", "en", "ar", "ca", "cs", "da", "de", "el", "en_AU", "en_GB", "es", "es_419", "fi", "fr",
+    "fr_CA", "he", "hi", "hr", "hu", "id", "it", "ja", "ko", "ms", "nl", "no", "pl", "pt_BR",
+    "pt_PT", "ro", "ru", "sk", "sv", "th", "tr", "uk", "vi", "zh_CN", "zh_HK", "zh_TW",
+];
+
+/// Environment variables selecting the locale, in decreasing precedence
+const VARS: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Locales to look names up in, most specific first, e.g. `de_CH`, `de`, `en`.
+/// The explicit locale is used if not empty, otherwise the locale of the environment.
+pub fn chain(explicit: &str) -> Vec<String> {
+    chain_with(explicit, |name| std::env::var(name).ok())
+}
+
+fn chain_with<F: Fn(&str) -> Option<String>>(explicit: &str, var: F) -> Vec<String> {
+    let locale = normalize(explicit).or_else(|| {
+        VARS.iter()
+            .filter_map(|name| var(name))
+            .find(|value| !value.is_empty())
+            .and_then(|value| normalize(&value))
+    });
+    let mut chain = Vec::new();
+    if let Some(locale) = locale {
+        if let Some((language, _)) = locale.split_once('_') {
+            chain.push(locale.to_owned());
+            chain.push(language.to_owned());
+        } else {
+            chain.push(locale);
+        }
+    }
+    if !chain.iter().any(|locale| locale == FALLBACK) {
+        chain.push(FALLBACK.to_owned());
+    }
+    chain
+}
+
+/// Strip the encoding and modifier, e.g. `de_CH.UTF-8@euro` to `de_CH`.
+/// `C` and `POSIX` stand for no locale.
+fn normalize(locale: &str) -> Option<String> {
+    let locale = locale.split(['.', '@']).next()?;
+    let locale = locale.trim().replace('-', "_");
+    match locale.as_str() {
+        "" | "C" | "POSIX" => None,
+        _ => Some(locale),
+    }
+}
+
+#[cfg(test)]
+mod locale_test {
+    use crate::configurator::locale::{chain_with, normalize};
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("de_CH.UTF-8@euro").as_deref(), Some("de_CH"));
+        assert_eq!(normalize("zh-Hans").as_deref(), Some("zh_Hans"));
+        assert_eq!(normalize("C.UTF-8"), None);
+        assert_eq!(normalize(""), None);
+    }
+
+    #[test]
+    fn test_chain_explicit() {
+        let chain = chain_with("de_CH", env(&[("LANG", "fr_FR.UTF-8")]));
+        assert_eq!(chain, vec!["de_CH", "de", "en"]);
+    }
+
+    #[test]
+    fn test_chain_env() {
+        let vars = &[
+            ("LANG", "fr_FR.UTF-8"),
+            ("LC_MESSAGES", "de_DE"),
+            ("LC_ALL", ""),
+        ];
+        assert_eq!(chain_with("", env(vars)), vec!["de_DE", "de", "en"]);
+        assert_eq!(
+            chain_with("", env(&[("LANG", "en_GB")])),
+            vec!["en_GB", "en"]
+        );
+        assert_eq!(chain_with("", env(&[("LANG", "C")])), vec!["en"]);
+    }
+}
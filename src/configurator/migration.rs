@@ -0,0 +1,208 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::configurator::diagnostics::{Diagnostic, Severity};
+use crate::utils;
+use crate::utils::serde::{deserialize_from_bytes, ensure_round_trip, serialize_as, Format};
+
+/// Version of the settings layout written by this build
+pub const VERSION: u64 = 2;
+
+/// Upgrade of a document by a single version, reporting every change made
+type Step = fn(&mut Map<String, Value>, &mut Vec<Diagnostic>);
+
+/// Steps in order, the first one upgrading unversioned documents from version 1
+const STEPS: &[Step] = &[nest_pref_names];
+
+/// Version of the document, 1 if it has none
+pub fn version_of(document: &Value) -> io::Result<u64> {
+    match document.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .filter(|version| *version > 0)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("version must be a positive integer, not {}", version),
+                )
+            }),
+    }
+}
+
+/// Upgrade the document step by step to the current version, and report what is changed.
+/// Documents written by a newer build are refused.
+pub fn migrate(document: &mut Value) -> io::Result<Vec<Diagnostic>> {
+    let version = version_of(document)?;
+    if version > VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "version {} is newer than the supported version {}",
+                version, VERSION
+            ),
+        ));
+    }
+    let fields = match document {
+        Value::Object(fields) => fields,
+        _ => return Ok(Vec::new()),
+    };
+    let mut changes = Vec::new();
+    if version == VERSION {
+        return Ok(changes);
+    }
+    for step in &STEPS[version as usize - 1..] {
+        step(fields, &mut changes);
+    }
+    // Keep the version on top of the document
+    let rest = std::mem::take(fields);
+    fields.insert("version".to_owned(), Value::from(VERSION));
+    fields.extend(rest.into_iter().filter(|(key, _)| key != "version"));
+    changes.push(Diagnostic::new(
+        Severity::Info,
+        "version",
+        format!("Upgraded from version {} to {}", version, VERSION),
+    ));
+    Ok(changes)
+}
+
+/// Upgrade the settings file, writing it back in its own format if `write` is set.
+/// A file whose comments or layout would be lost in rewriting it is left alone unless `force`.
+pub fn migrate_file<P: AsRef<Path>>(
+    path: P,
+    write: bool,
+    force: bool,
+) -> io::Result<Vec<Diagnostic>> {
+    let path = path.as_ref();
+    let content = fs::read(path)?;
+    let format = Format::detect(&content);
+    let mut document: Value = deserialize_from_bytes(&content)?;
+    if write && !force {
+        ensure_round_trip(format, &content, &document)?;
+    }
+    let changes = migrate(&mut document)?;
+    if write && !changes.is_empty() {
+        utils::write_atomically(path, &serialize_as(format, &document)?)?;
+    }
+    Ok(changes)
+}
+
+/// Version 2: names of the preference panes are keyed by locale,
+/// so names keyed by the pane move under `en`
+fn nest_pref_names(document: &mut Map<String, Value>, changes: &mut Vec<Diagnostic>) {
+    let names = match document
+        .get_mut("internal")
+        .and_then(|internal| internal.get_mut("prefNames"))
+        .and_then(Value::as_object_mut)
+    {
+        Some(names) => names,
+        None => return,
+    };
+    let panes = names
+        .iter()
+        .filter(|(_, name)| name.is_string())
+        .map(|(pane, _)| pane.to_owned())
+        .collect::<Vec<_>>();
+    if panes.is_empty() {
+        return;
+    }
+    let mut english = match names.remove("en") {
+        Some(Value::Object(english)) => english,
+        _ => Map::new(),
+    };
+    for pane in panes {
+        if let Some(name) = names.remove(&pane) {
+            changes.push(Diagnostic::new(
+                Severity::Info,
+                format!("internal.prefNames.{}", pane),
+                format!("Moved to internal.prefNames.en.{}", pane),
+            ));
+            english.insert(pane, name);
+        }
+    }
+    names.insert("en".to_owned(), Value::Object(english));
+}
+
+#[cfg(test)]
+mod migration_test {
+    use std::fs;
+
+    use serde_json::{json, Value};
+
+    use crate::configurator::migration::{migrate, migrate_file, VERSION};
+
+    #[test]
+    fn test_migrate_pref_names() {
+        let mut document = json!({
+            "internal": {"prefNames": {"de": {"Speech": "Siri"}, "Speech": "Voice"}}
+        });
+        let changes = migrate(&mut document).expect("Failed to migrate");
+        let expected = json!({
+            "version": VERSION,
+            "internal": {"prefNames": {"de": {"Speech": "Siri"}, "en": {"Speech": "Voice"}}}
+        });
+        assert_eq!(document, expected);
+        let keys = changes
+            .iter()
+            .map(|change| change.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["internal.prefNames.Speech", "version"]);
+        let first = document.as_object().and_then(|fields| fields.keys().next());
+        assert_eq!(first.map(String::as_str), Some("version"));
+    }
+
+    #[test]
+    fn test_migrate_current() {
+        let mut document =
+            json!({"version": VERSION, "internal": {"prefNames": {"Speech": "Voice"}}});
+        let expected = document.clone();
+        assert!(migrate(&mut document)
+            .expect("Failed to migrate")
+            .is_empty());
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn test_migrate_unsupported() {
+        assert!(migrate(&mut json!({ "version": VERSION + 1 })).is_err());
+        assert!(migrate(&mut json!({"version": "2"})).is_err());
+    }
+
+    #[test]
+    fn test_migrate_file() {
+        let path = std::env::temp_dir().join(format!("migration-{}.yaml", std::process::id()));
+        fs::write(&path, "internal:\n  prefNames:\n    Speech: Voice\n").expect("Failed to write");
+        assert_eq!(migrate_file(&path, false, false).expect("Failed").len(), 2);
+        assert_eq!(migrate_file(&path, true, false).expect("Failed").len(), 2);
+        assert!(migrate_file(&path, false, false)
+            .expect("Failed")
+            .is_empty());
+        let content = fs::read_to_string(&path).expect("Failed to read");
+        let document: Value = serde_yaml::from_str(&content).expect("Failed to parse");
+        assert_eq!(
+            document["internal"]["prefNames"]["en"]["Speech"],
+            json!("Voice")
+        );
+        fs::remove_file(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_migrate_file_comments() {
+        let path =
+            std::env::temp_dir().join(format!("migration-comments-{}.yaml", std::process::id()));
+        let content = "internal:\n  prefNames:\n    Speech: Voice  # Renamed\n";
+        fs::write(&path, content).expect("Failed to write");
+        assert_eq!(migrate_file(&path, false, false).expect("Failed").len(), 2);
+        let error = migrate_file(&path, true, false).expect_err("Comments dropped");
+        assert!(error.to_string().contains("--force"));
+        assert_eq!(fs::read_to_string(&path).expect("Failed to read"), content);
+        assert_eq!(migrate_file(&path, true, true).expect("Failed").len(), 2);
+        assert!(migrate_file(&path, false, false)
+            .expect("Failed")
+            .is_empty());
+        fs::remove_file(path).expect("Failed to clean up");
+    }
+}
@@ -1,5 +1,19 @@
+pub use alias::{Alias, Aliases};
 #[cfg(test)]
-pub use configs::configs_test::get_content;
+pub use configs::configs_test::get_configs;
 pub use configs::Configs;
+pub use diagnostics::{diagnose, validate, Diagnostic, Severity};
+pub use editor::{edit_file, Edit};
+pub use loader::Loader;
+pub use migration::{migrate, migrate_file, VERSION};
+pub use root::Root;
 
+mod alias;
 mod configs;
+mod diagnostics;
+mod editor;
+mod loader;
+mod locale;
+mod migration;
+mod root;
+pub(crate) mod schema;
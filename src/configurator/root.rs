@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::configurator::schema::Schema;
+use crate::utils;
+use crate::utils::UndefinedVariable;
+
+/// Folder walked for bundles, along with how it is walked.
+/// Given either as a plain path, or as an object with a `path` and the options to change.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(from = "RootEntry")]
+pub struct Root {
+    pub path: PathBuf,
+    /// How many folders deep to walk into, unlimited if not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    #[serde(rename = "followSymlinks")]
+    pub follow_symlinks: bool,
+    /// File extensions of the bundles listed under the root, instead of the default ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<String>>,
+    /// Label attached to every service found under the root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Roots with a higher priority are walked, and their services listed, first
+    pub priority: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RootEntry {
+    Path(PathBuf),
+    Options(RootOptions),
+}
+
+#[derive(Deserialize)]
+struct RootOptions {
+    path: PathBuf,
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(default, rename = "followSymlinks")]
+    follow_symlinks: bool,
+    #[serde(default)]
+    extensions: Option<Vec<String>>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    priority: i64,
+}
+
+impl Root {
+    /// Root with the environment variables and the leading tilde of its path expanded
+    pub(crate) fn expanded(&self) -> Result<Root, UndefinedVariable> {
+        Ok(Root {
+            path: utils::expand_path(&self.path)?,
+            ..self.clone()
+        })
+    }
+}
+
+impl Schema for Root {
+    fn schema() -> Value {
+        json!({
+            "anyOf": [
+                PathBuf::schema(),
+                {
+                    "type": "object",
+                    "properties": {
+                        "path": PathBuf::schema(),
+                        "depth": u64::schema(),
+                        "followSymlinks": bool::schema(),
+                        "extensions": Vec::<String>::schema(),
+                        "label": String::schema(),
+                        "priority": i64::schema()
+                    },
+                    "required": ["path"],
+                    "additionalProperties": false
+                }
+            ]
+        })
+    }
+}
+
+impl From<PathBuf> for Root {
+    fn from(path: PathBuf) -> Self {
+        Root {
+            path,
+            depth: None,
+            follow_symlinks: false,
+            extensions: None,
+            label: None,
+            priority: 0,
+        }
+    }
+}
+
+impl From<RootEntry> for Root {
+    fn from(entry: RootEntry) -> Self {
+        match entry {
+            RootEntry::Path(path) => Root::from(path),
+            RootEntry::Options(options) => Root {
+                path: options.path,
+                depth: options.depth,
+                follow_symlinks: options.follow_symlinks,
+                extensions: options.extensions,
+                label: options.label,
+                priority: options.priority,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod root_test {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use crate::configurator::root::Root;
+
+    #[test]
+    fn test_plain_path() {
+        let root: Root = serde_json::from_value(json!("/Applications")).expect("Failed to parse");
+        assert_eq!(root, Root::from(PathBuf::from("/Applications")));
+    }
+
+    #[test]
+    fn test_options() {
+        let root: Root = serde_json::from_value(json!({
+            "path": "/System/Library/CoreServices/Applications",
+            "depth": 1,
+            "extensions": ["app"],
+            "label": "Core",
+            "priority": -1
+        }))
+        .expect("Failed to parse");
+        assert_eq!(root.depth, Some(1));
+        assert!(!root.follow_symlinks);
+        assert_eq!(root.extensions, Some(vec!["app".to_owned()]));
+        assert_eq!(root.label.as_deref(), Some("Core"));
+        assert_eq!(root.priority, -1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(serde_json::from_value::<Root>(json!({"depth": 1})).is_err());
+        assert!(serde_json::from_value::<Root>(json!({"path": "/a", "depth": -1})).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+/// JSON Schema of a configurable value type.
+/// Types with a fixed set of values list them under `enum`.
+pub trait Schema {
+    fn schema() -> Value;
+
+    /// Every value of the type, if it is a closed set
+    fn allowed_values() -> Option<Vec<Value>> {
+        None
+    }
+}
+
+/// Schema of the type, with its allowed values if any
+pub fn of<T: Schema>() -> Value {
+    let mut schema = T::schema();
+    if let Some(values) = T::allowed_values() {
+        schema["enum"] = Value::Array(values);
+    }
+    schema
+}
+
+impl Schema for bool {
+    fn schema() -> Value {
+        json!({"type": "boolean"})
+    }
+}
+
+impl Schema for u64 {
+    fn schema() -> Value {
+        json!({"type": "integer", "minimum": 0})
+    }
+}
+
+impl Schema for i64 {
+    fn schema() -> Value {
+        json!({"type": "integer"})
+    }
+}
+
+impl Schema for String {
+    fn schema() -> Value {
+        json!({"type": "string"})
+    }
+}
+
+impl Schema for PathBuf {
+    fn schema() -> Value {
+        json!({"type": "string", "format": "path"})
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": of::<T>()})
+    }
+}
+
+impl<T: Schema> Schema for HashSet<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": of::<T>(), "uniqueItems": true})
+    }
+}
+
+impl<T: Schema> Schema for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": of::<T>()})
+    }
+}
+
+/// Schema of a single configurable option, wrapping the schema of its value.
+/// `examples` suggests common values of the option, without ruling out others.
+pub fn option<T: Schema>(
+    display_name: &str,
+    description: &str,
+    default: Value,
+    examples: Option<&[&str]>,
+) -> Value {
+    let mut value = of::<T>();
+    if let Some(examples) = examples {
+        value["examples"] = json!(examples);
+    }
+    value["default"] = default;
+    json!({
+        "title": display_name,
+        "description": description,
+        "type": "object",
+        "properties": {
+            "displayName": {"type": "string", "default": display_name},
+            "value": value
+        },
+        "required": ["value"],
+        "additionalProperties": false
+    })
+}
+
+#[cfg(test)]
+mod schema_test {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use serde_json::{json, Value};
+
+    use crate::configurator::schema::{of, option, Schema};
+
+    struct Switch;
+
+    impl Schema for Switch {
+        fn schema() -> Value {
+            json!({"type": "string"})
+        }
+
+        fn allowed_values() -> Option<Vec<Value>> {
+            Some(vec![json!("on"), json!("off")])
+        }
+    }
+
+    #[test]
+    fn test_schema() {
+        assert_eq!(bool::schema(), json!({"type": "boolean"}));
+        assert_eq!(
+            Vec::<String>::schema(),
+            json!({"type": "array", "items": {"type": "string"}})
+        );
+        assert_eq!(HashSet::<PathBuf>::schema()["uniqueItems"], json!(true));
+        assert!(of::<String>().get("enum").is_none());
+    }
+
+    #[test]
+    fn test_allowed_values() {
+        assert_eq!(of::<Switch>()["enum"], json!(["on", "off"]));
+        assert_eq!(
+            Vec::<Switch>::schema()["items"]["enum"],
+            json!(["on", "off"])
+        );
+    }
+
+    #[test]
+    fn test_option_examples() {
+        let option = option::<String>("Mode", "", json!("a"), Some(&["a", "b"]));
+        assert_eq!(option["properties"]["value"]["examples"], json!(["a", "b"]));
+        assert!(option["properties"]["value"].get("enum").is_none());
+    }
+
+    #[test]
+    fn test_option() {
+        let option = option::<u64>("Depth", "How deep to walk", json!(3), None);
+        assert_eq!(option["title"], json!("Depth"));
+        assert_eq!(
+            option["properties"]["displayName"]["default"],
+            json!("Depth")
+        );
+        assert_eq!(
+            option["properties"]["value"],
+            json!({"type": "integer", "minimum": 0, "default": 3})
+        );
+    }
+}
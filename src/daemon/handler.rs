@@ -0,0 +1,191 @@
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::configurator::Configs;
+use crate::daemon::protocol::{
+    QueryParams, Request, Response, TargetParams, EXECUTION_ERROR, INVALID_PARAMS,
+    METHOD_NOT_FOUND, PARSE_ERROR,
+};
+use crate::execute;
+use crate::query::Catalog;
+
+/// Resident state answering requests from the collected catalog, shared by all clients
+pub struct Handler {
+    state: RwLock<State>,
+}
+
+/// Configs and the catalog collected from them, always swapped together
+struct State {
+    configs: Arc<Configs>,
+    catalog: Catalog,
+}
+
+impl Handler {
+    /// New handler with a freshly walked catalog
+    pub fn new(configs: Configs) -> Self {
+        let catalog = Catalog::new(&configs);
+        Self::with_catalog(configs, catalog)
+    }
+
+    /// New handler answering from an already collected catalog
+    pub fn with_catalog(configs: Configs, catalog: Catalog) -> Self {
+        Handler {
+            state: RwLock::new(State {
+                configs: Arc::new(configs),
+                catalog,
+            }),
+        }
+    }
+
+    /// Replace the configs, and answer subsequent requests from a catalog collected with them
+    pub fn reload(&self, configs: Configs) {
+        let catalog = Catalog::new(&configs);
+        *self.state.write().unwrap_or_else(PoisonError::into_inner) = State {
+            configs: Arc::new(configs),
+            catalog,
+        };
+    }
+
+    /// Walk the paths again with the current configs
+    fn refresh(&self) {
+        let configs = self.state().configs.clone();
+        let catalog = Catalog::new(&configs);
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        // Configs reloaded during the walk come with their own catalog, which is kept
+        if Arc::ptr_eq(&state.configs, &configs) {
+            state.catalog = catalog;
+        }
+    }
+
+    fn state(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Handle a single raw request line
+    pub fn handle_line(&self, line: &str) -> Response {
+        match serde_json::from_str::<Request>(line) {
+            Ok(request) => self.handle(request),
+            Err(error) => Response::error(Value::Null, PARSE_ERROR, error.to_string()),
+        }
+    }
+
+    pub fn handle(&self, request: Request) -> Response {
+        let Request { id, method, params } = request;
+        let result = match method.as_str() {
+            "query" => parse_params(params).and_then(|params: QueryParams| {
+                let state = self.state();
+                let services = state.catalog.query(&params.q).collect::<Vec<_>>();
+                serde_json::to_value(services).map_err(|error| (EXECUTION_ERROR, error.to_string()))
+            }),
+            "execute" | "reveal" => parse_params(params).and_then(|params: TargetParams| {
+                let configs = self.state().configs.clone();
+                execute::execute(&params.id, method == "reveal", &configs)
+                    .map(|_| Value::Null)
+                    .map_err(|error| (EXECUTION_ERROR, error.to_string()))
+            }),
+            "refresh" => {
+                self.refresh();
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+        };
+        match result {
+            Ok(result) => Response::result(id, result),
+            Err((code, message)) => Response::error(id, code, message),
+        }
+    }
+}
+
+fn parse_params<D: DeserializeOwned>(params: Value) -> Result<D, (i32, String)> {
+    serde_json::from_value(params).map_err(|error| (INVALID_PARAMS, error.to_string()))
+}
+
+#[cfg(test)]
+mod handler_test {
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::configurator::{get_configs, Loader};
+    use crate::daemon::handler::Handler;
+    use crate::daemon::protocol::{INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR};
+    use crate::query::{Catalog, Kind, Service};
+
+    fn handler() -> Handler {
+        let configs = get_configs();
+        let catalog: Catalog = vec![
+            Service::new(
+                "/System/Applications/Books.app",
+                Kind::Application,
+                &configs,
+            ),
+            Service::new(
+                "/System/Applications/Safari.app",
+                Kind::Application,
+                &configs,
+            ),
+        ]
+        .into();
+        Handler::with_catalog(configs, catalog)
+    }
+
+    #[test]
+    fn test_handle_query() {
+        let response = handler().handle_line(r#"{"id":7,"method":"query","params":{"q":"saf"}}"#);
+        assert_eq!(response.id, json!(7));
+        let expected = json!([{
+            "title": "Safari",
+            "subtitle": "/System/Applications/Safari.app",
+            "id": "/System/Applications/Safari.app",
+            "kind": "application"
+        }]);
+        assert_eq!(response.result, Some(expected));
+    }
+
+    #[test]
+    fn test_handle_unknown_method() {
+        let response = handler().handle_line(r#"{"id":1,"method":"launch"}"#);
+        assert_eq!(
+            response.error.map(|error| error.code),
+            Some(METHOD_NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn test_handle_invalid_params() {
+        let response = handler().handle_line(r#"{"id":1,"method":"query","params":{}}"#);
+        assert_eq!(response.error.map(|error| error.code), Some(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn test_handle_malformed() {
+        let response = handler().handle_line("saf");
+        assert_eq!(response.error.map(|error| error.code), Some(PARSE_ERROR));
+    }
+
+    #[test]
+    fn test_reload() {
+        let root = std::env::temp_dir().join(format!("handler-reload-{}", std::process::id()));
+        fs::create_dir_all(root.join("Zed.app")).expect("Failed to create");
+        let handler = handler();
+        let settings = format!(r#"{{"internal":{{"paths":[{:?}]}}}}"#, root);
+        handler.reload(
+            Loader::new()
+                .content(settings)
+                .load()
+                .expect("Failed to load"),
+        );
+        let query = |q: &str| {
+            let line = format!(r#"{{"id":1,"method":"query","params":{{"q":"{}"}}}}"#, q);
+            handler.handle_line(&line).result
+        };
+        assert_eq!(query("saf"), Some(json!([])));
+        assert_eq!(
+            query("zed").and_then(|result| result.as_array().map(Vec::len)),
+            Some(1)
+        );
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+}
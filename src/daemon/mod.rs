@@ -0,0 +1,43 @@
+use std::io::{self, stdin, stdout};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+pub use handler::Handler;
+
+use crate::configurator::{Configs, Loader};
+
+mod handler;
+mod protocol;
+mod socket;
+mod stdio;
+mod watcher;
+
+/// Serve line-delimited JSON requests from stdin until it is closed.
+/// The configs are reloaded from the loader whenever one of its settings files changes.
+pub fn serve(configs: Configs, loader: Loader) {
+    let handler = Arc::new(Handler::new(configs));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    watcher::watch(handler.clone(), loader, shutdown.clone());
+    stdio::serve(&handler, stdin().lock(), stdout().lock());
+    shutdown.store(true, Ordering::SeqCst);
+}
+
+/// Serve line-delimited JSON requests from every client connected to the Unix domain socket,
+/// until SIGTERM or SIGINT is received, or the socket file is removed.
+/// The configs are reloaded from the loader whenever one of its settings files changes.
+pub fn listen(configs: Configs, loader: Loader, path: Option<PathBuf>) -> io::Result<()> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    for signal in &[SIGTERM, SIGINT] {
+        signal_hook::flag::register(*signal, shutdown.clone())?;
+    }
+    let path = path.unwrap_or_else(socket::default_path);
+    let handler = Arc::new(Handler::new(configs));
+    let watcher = watcher::watch(handler.clone(), loader, shutdown.clone());
+    let result = socket::listen(handler, path, shutdown.clone());
+    shutdown.store(true, Ordering::SeqCst);
+    let _ = watcher.join();
+    result
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PARSE_ERROR: i32 = -32700;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const EXECUTION_ERROR: i32 = -32000;
+
+/// A single request line, e.g. `{"id":1,"method":"query","params":{"q":"saf"}}`
+#[derive(Deserialize, Debug)]
+pub struct Request {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QueryParams {
+    pub q: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TargetParams {
+    pub id: String,
+}
+
+/// A single response line, tagged with the id of the request it answers
+#[derive(Serialize, Debug)]
+pub struct Response {
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ResponseError>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ResponseError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl Response {
+    pub fn result(id: Value, result: Value) -> Self {
+        Response {
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error<S: Into<String>>(id: Value, code: i32, message: S) -> Self {
+        Response {
+            id,
+            result: None,
+            error: Some(ResponseError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod protocol_test {
+    use serde_json::{json, Value};
+
+    use crate::daemon::protocol::{Request, Response};
+
+    #[test]
+    fn test_deserialize_request() {
+        let source = r#"{"id":1,"method":"query","params":{"q":"saf"}}"#;
+        let request: Request = serde_json::from_str(source).expect("Unable to deserialize");
+        assert_eq!(request.id, json!(1));
+        assert_eq!(request.method, "query");
+        assert_eq!(request.params, json!({"q": "saf"}));
+    }
+
+    #[test]
+    fn test_deserialize_request_without_params() {
+        let source = r#"{"method":"refresh"}"#;
+        let request: Request = serde_json::from_str(source).expect("Unable to deserialize");
+        assert_eq!(request.id, Value::Null);
+        assert_eq!(request.params, Value::Null);
+    }
+
+    #[test]
+    fn test_serialize_response() {
+        let response = Response::result(json!("a"), json!([]));
+        let serialized = serde_json::to_string(&response).expect("Unable to serialize");
+        assert_eq!(serialized, r#"{"id":"a","result":[]}"#);
+    }
+
+    #[test]
+    fn test_serialize_error() {
+        let response = Response::error(json!(2), -32601, "Unknown method");
+        let serialized = serde_json::to_string(&response).expect("Unable to serialize");
+        let expected = r#"{"id":2,"error":{"code":-32601,"message":"Unknown method"}}"#;
+        assert_eq!(serialized, expected);
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::daemon::handler::Handler;
+use crate::utils::serde::serialize_to_string;
+
+/// How often blocked accepts and reads wake up to check for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default socket path, under `XDG_RUNTIME_DIR` when available
+pub fn default_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(concat!(env!("CARGO_PKG_NAME"), ".sock"))
+}
+
+/// Accept clients on the socket until `shutdown` is set or the socket file is removed.
+/// Every client is served on its own thread, sharing the same handler.
+pub fn listen<P: AsRef<Path>>(
+    handler: Arc<Handler>,
+    path: P,
+    shutdown: Arc<AtomicBool>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let listener = bind(path)?;
+    listener.set_nonblocking(true)?;
+    let mut clients = Vec::new();
+    while !shutdown.load(Ordering::SeqCst) {
+        if !path.exists() {
+            eprintln!("Socket file is removed, shutting down");
+            break;
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let handler = handler.clone();
+                let shutdown = shutdown.clone();
+                clients.push(thread::spawn(move || {
+                    if let Err(error) = serve_client(&handler, stream, &shutdown) {
+                        eprintln!("Client error: {}", error);
+                    }
+                }));
+            }
+            Err(ref error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(error) => eprintln!("Failed to accept: {}", error),
+        }
+        clients.retain(|client| !client.is_finished());
+    }
+    shutdown.store(true, Ordering::SeqCst);
+    for client in clients {
+        let _ = client.join();
+    }
+    match fs::remove_file(path) {
+        Err(ref error) if error.kind() != ErrorKind::NotFound => {
+            eprintln!("Failed to remove socket: {}", error)
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Bind the socket, replacing a stale socket file left by a dead process
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("{} is served by another process", path.display()),
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Answer line-delimited requests from one client until it disconnects or shutdown is set
+fn serve_client(handler: &Handler, stream: UnixStream, shutdown: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut output = stream.try_clone()?;
+    let mut input = BufReader::new(stream);
+    let mut line = Vec::new();
+    while !shutdown.load(Ordering::SeqCst) {
+        match input.read_until(b'\n', &mut line) {
+            Ok(0) if line.is_empty() => break,
+            // The last request may end at EOF without a newline
+            Ok(0) => (),
+            Err(ref error) if error.kind() == ErrorKind::ConnectionReset => break,
+            Ok(_) if !line.ends_with(b"\n") => continue,
+            Ok(_) => (),
+            Err(ref error)
+                if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut =>
+            {
+                continue
+            }
+            Err(error) => return Err(error),
+        }
+        let request = String::from_utf8_lossy(&line).into_owned();
+        line.clear();
+        if request.trim().is_empty() {
+            continue;
+        }
+        let response = serialize_to_string(&handler.handle_line(&request))
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod socket_test {
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixStream;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::configurator::get_configs;
+    use crate::daemon::handler::Handler;
+    use crate::daemon::socket::listen;
+    use crate::query::{Catalog, Kind, Service};
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "launch_service-{}-{}.sock",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn start(path: &Path, shutdown: &Arc<AtomicBool>) -> thread::JoinHandle<()> {
+        let configs = get_configs();
+        let catalog: Catalog = vec![Service::new(
+            "/System/Applications/Books.app",
+            Kind::Application,
+            &configs,
+        )]
+        .into();
+        let handler = Arc::new(Handler::with_catalog(configs, catalog));
+        let (socket, shutdown) = (path.to_path_buf(), shutdown.clone());
+        let server =
+            thread::spawn(move || listen(handler, socket, shutdown).expect("Failed to listen"));
+        while UnixStream::connect(path).is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        server
+    }
+
+    fn request(path: &Path, line: &str) -> String {
+        let mut stream = UnixStream::connect(path).expect("Failed to connect");
+        writeln!(stream, "{}", line).expect("Failed to write");
+        let mut response = String::new();
+        BufReader::new(stream)
+            .read_line(&mut response)
+            .expect("Failed to read");
+        response
+    }
+
+    #[test]
+    fn test_concurrent_clients() {
+        let path = socket_path("clients");
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let server = start(&path, &shutdown);
+        let clients = (0..4)
+            .map(|id| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let line = format!(r#"{{"id":{},"method":"query","params":{{"q":"bo"}}}}"#, id);
+                    request(&path, &line)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (id, client) in clients.into_iter().enumerate() {
+            let response = client.join().expect("Client panicked");
+            assert!(response.starts_with(&format!(r#"{{"id":{},"result":[{{"title":"Books""#, id)));
+        }
+        shutdown.store(true, Ordering::SeqCst);
+        server.join().expect("Server panicked");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_request_without_newline() {
+        let path = socket_path("eof");
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let server = start(&path, &shutdown);
+        let mut stream = UnixStream::connect(&path).expect("Failed to connect");
+        write!(
+            stream,
+            r#"{{"id":1,"method":"query","params":{{"q":"bo"}}}}"#
+        )
+        .expect("Failed to write");
+        stream.shutdown(Shutdown::Write).expect("Failed to close");
+        let mut response = String::new();
+        BufReader::new(stream)
+            .read_line(&mut response)
+            .expect("Failed to read");
+        assert!(response.starts_with(r#"{"id":1,"result":[{"title":"Books""#));
+        shutdown.store(true, Ordering::SeqCst);
+        server.join().expect("Server panicked");
+    }
+
+    #[test]
+    fn test_shutdown_on_socket_removed() {
+        let path = socket_path("removed");
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let server = start(&path, &shutdown);
+        fs::remove_file(&path).expect("Failed to remove socket");
+        server.join().expect("Server panicked");
+        assert!(shutdown.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::{BufRead, Write};
+
+use crate::daemon::handler::Handler;
+use crate::utils::serde::serialize_to_string;
+
+/// Answer every request line from `input` with a response line on `output`
+pub fn serve<R: BufRead, W: Write>(handler: &Handler, input: R, mut output: W) {
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                eprintln!("Failed to read: {}", error);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serialize_to_string(&handler.handle_line(&line)) {
+            Ok(response) => response,
+            Err(error) => {
+                eprintln!("Failed to serialize: {}", error);
+                continue;
+            }
+        };
+        if let Err(error) = writeln!(output, "{}", response).and_then(|_| output.flush()) {
+            eprintln!("Failed to write: {}", error);
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod stdio_test {
+    use crate::configurator::get_configs;
+    use crate::daemon::handler::Handler;
+    use crate::daemon::stdio::serve;
+    use crate::query::{Catalog, Kind, Service};
+
+    #[test]
+    fn test_serve() {
+        let configs = get_configs();
+        let catalog: Catalog = vec![Service::new(
+            "/System/Applications/Books.app",
+            Kind::Application,
+            &configs,
+        )]
+        .into();
+        let handler = Handler::with_catalog(configs, catalog);
+        let input = "{\"id\":1,\"method\":\"query\",\"params\":{\"q\":\"boo\"}}\n\n{\"id\":2,\"method\":\"query\",\"params\":{\"q\":\"x\"}}\n";
+        let mut output = Vec::new();
+        serve(&handler, input.as_bytes(), &mut output);
+        let string = String::from_utf8(output).expect("Failed to parse");
+        let expected = r#"{"id":1,"result":[{"title":"Books","subtitle":"/System/Applications/Books.app","id":"/System/Applications/Books.app","kind":"application"}]}
+{"id":2,"result":[]}
+"#;
+        assert_eq!(string, expected);
+    }
+}
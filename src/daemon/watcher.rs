@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::configurator::{validate, Diagnostic, Loader, Severity};
+use crate::daemon::handler::Handler;
+use crate::utils::serde::serialize_to_string;
+
+/// How often the settings files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Modification times of the watched settings files
+struct Stamps {
+    files: Vec<PathBuf>,
+    stamps: Vec<Option<SystemTime>>,
+}
+
+impl Stamps {
+    fn new(files: Vec<PathBuf>) -> Self {
+        let stamps = files.iter().map(modified).collect();
+        Stamps { files, stamps }
+    }
+
+    /// Whether any file is created, modified or removed since the last call
+    fn changed(&mut self) -> bool {
+        let stamps: Vec<_> = self.files.iter().map(modified).collect();
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reload the handler from the loader, keeping the previous configs if the new ones are invalid
+pub fn reload(handler: &Handler, loader: &Loader) -> Result<(), Vec<Diagnostic>> {
+    let document = loader
+        .merged()
+        .map_err(|error| vec![Diagnostic::new(Severity::Error, "", error.to_string())])?;
+    let (configs, _) = validate(&document)?;
+    handler.reload(configs);
+    Ok(())
+}
+
+/// Reload the handler whenever a settings file of the loader changes, until shutdown is set
+pub fn watch(
+    handler: Arc<Handler>,
+    loader: Loader,
+    shutdown: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let files = loader.files().into_iter().map(PathBuf::from).collect();
+        let mut stamps = Stamps::new(files);
+        while !shutdown.load(Ordering::SeqCst) {
+            thread::sleep(POLL_INTERVAL);
+            if !stamps.changed() {
+                continue;
+            }
+            if let Err(diagnostics) = reload(&handler, &loader) {
+                eprintln!("Failed to reload settings, keeping the previous ones");
+                for diagnostic in diagnostics {
+                    if let Ok(diagnostic) = serialize_to_string(&diagnostic) {
+                        eprintln!("{}", diagnostic);
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod watcher_test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    use crate::configurator::{get_configs, Loader};
+    use crate::daemon::handler::Handler;
+    use crate::daemon::watcher::{reload, Stamps};
+    use crate::query::Catalog;
+
+    fn settings_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("watcher-{}-{}.yaml", std::process::id(), name))
+    }
+
+    fn handler() -> Handler {
+        Handler::with_catalog(get_configs(), Catalog::from(Vec::new()))
+    }
+
+    #[test]
+    fn test_changed() {
+        let path = settings_file("changed");
+        let mut stamps = Stamps::new(vec![path.clone()]);
+        assert!(!stamps.changed());
+        fs::write(&path, "internal: {}").expect("Failed to write");
+        assert!(stamps.changed());
+        assert!(!stamps.changed());
+        let file = fs::File::options()
+            .write(true)
+            .open(&path)
+            .expect("Failed to open");
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .expect("Failed to touch");
+        assert!(stamps.changed());
+        fs::remove_file(&path).expect("Failed to clean up");
+        assert!(stamps.changed());
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = settings_file("reload");
+        fs::write(&path, "internal:\n  paths: []\n").expect("Failed to write");
+        let handler = handler();
+        assert!(reload(&handler, &Loader::new().file(&path)).is_ok());
+        fs::remove_file(&path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_reload_invalid() {
+        let path = settings_file("invalid");
+        fs::write(&path, "internal:\n  paths: 1\n").expect("Failed to write");
+        let handler = handler();
+        let diagnostics = reload(&handler, &Loader::new().file(&path)).err();
+        assert_eq!(diagnostics.map(|diagnostics| diagnostics.len()), Some(1));
+        fs::write(&path, "internal: [").expect("Failed to write");
+        assert!(reload(&handler, &Loader::new().file(&path)).is_err());
+        fs::remove_file(&path).expect("Failed to clean up");
+    }
+}
@@ -1,14 +1,73 @@
-use std::process::Command;
+use std::io::{self, Error};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
 
-pub fn execute(id: &str, is_alter: bool) {
-    let mut command = Command::new("open");
-    let res = if is_alter {
-        command.arg("-R").arg(id)
+use crate::configurator::Configs;
+use crate::query::desktop::{self, DesktopEntry};
+
+/// Opener of files and folders on the platform
+#[cfg(target_os = "macos")]
+const OPEN: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const OPEN: &str = "xdg-open";
+
+/// Open the service, or reveal it if `is_alter`.
+/// Desktop entries are named after the locales of the configs.
+pub fn execute(id: &str, is_alter: bool, configs: &Configs) -> io::Result<()> {
+    // Actions of desktop entries are identified by the desktop file and the action
+    let (path, action) = match desktop::split_action_id(id) {
+        Some((path, action)) => (path, Some(action)),
+        None => (Path::new(id), None),
+    };
+    if is_alter {
+        return reveal(path);
+    }
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "desktop")
+    {
+        return launch_desktop_entry(path, action, configs.get_locales());
+    }
+    spawn_detached(Command::new(OPEN).arg(id))
+}
+
+/// Show the item in the file manager, selecting it where the platform can
+fn reveal(path: &Path) -> io::Result<()> {
+    let mut command = Command::new(OPEN);
+    if cfg!(target_os = "macos") {
+        command.arg("-R").arg(path);
     } else {
-        command.arg(id)
+        command.arg(path.parent().unwrap_or(path));
     }
-    .spawn();
-    if let Err(error) = res {
-        println!("{}", error)
+    spawn_detached(&mut command)
+}
+
+/// Start the command without waiting for it, so a slow opener holds up no request.
+/// The child is reaped on a thread of its own, and its output kept off the JSON on stdout.
+fn spawn_detached(command: &mut Command) -> io::Result<()> {
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::null()).spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Run the `Exec` command line of the desktop entry, or of one of its actions,
+/// without waiting for the application
+fn launch_desktop_entry(path: &Path, action: Option<&str>, locales: &[String]) -> io::Result<()> {
+    let entry = DesktopEntry::read(path)?;
+    let action =
+        match action {
+            Some(action) => Some(entry.action(action).ok_or_else(|| {
+                Error::other(format!("No action {} in {}", action, path.display()))
+            })?),
+            None => None,
+        };
+    let arguments = desktop::command_line(&entry, action, path, locales)
+        .ok_or_else(|| Error::other(format!("Invalid Exec line in {}", path.display())))?;
+    let mut command = Command::new(&arguments[0]);
+    command.args(&arguments[1..]).stderr(Stdio::null());
+    if let Some(folder) = entry.get("Path").filter(|folder| !folder.is_empty()) {
+        command.current_dir(folder);
     }
+    spawn_detached(&mut command)
 }
@@ -0,0 +1,28 @@
+//! Discovery of installed applications and preference panes.
+//!
+//! Every entry point takes the [`Configs`] explicitly, so discovery can be embedded
+//! without setting `SETTINGS` in the process environment:
+//!
+//! ```no_run
+//! use launch_service::{Catalog, Loader};
+//!
+//! let configs = Loader::new().file("settings.json").load()?;
+//! for service in Catalog::new(&configs).query("saf") {
+//!     println!("{}", service.title);
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+pub use configurator::{
+    diagnose, edit_file, migrate, migrate_file, validate, Alias, Aliases, Configs, Diagnostic,
+    Edit, Loader, Root, Severity, VERSION,
+};
+pub use manifest::Manifest;
+pub use query::{match_query, tokenize, Catalog, Exclusion, Kind, QueryProcessor, Service};
+
+mod configurator;
+pub mod daemon;
+pub mod execute;
+pub mod manifest;
+pub mod query;
+mod utils;
@@ -1,26 +1,177 @@
-use lazy_static::lazy_static;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
-mod configurator;
-mod execute;
-mod query;
-mod utils;
+use launch_service::manifest::PROFILE_NAME;
+use launch_service::{
+    daemon, diagnose, edit_file, execute, migrate_file, query, Configs, Loader, Manifest, Severity,
+};
 
-lazy_static! {
-    pub static ref CONFIG: configurator::Configs = configurator::Configs::from(
-        std::env::var("SETTINGS").expect("Failed to get SETTINGS from environment")
-    )
-    .expect("settings is invalid");
-}
+use cli::{Command, Error, Options};
+
+mod cli;
 
 fn main() {
-    let mut args = std::env::args();
-    let _ = args.next();
-    let action = args.next().expect("Action flag is missing");
-    let content = args.next().expect("Content is missing");
-    match action.trim() {
-        "-q" | "--query" => query::query(&content),
-        "-x" | "--execute" => execute::execute(&content, false),
-        "-X" | "--alter-execute" => execute::execute(&content, true),
-        _ => panic!("Unexpected flag"),
+    let (options, args) =
+        Options::extract(std::env::args().skip(1)).unwrap_or_else(|error| error.exit());
+    let command = Command::parse(args).unwrap_or_else(|error| error.exit());
+    if let Err(error) = run(command, &options) {
+        error.exit();
+    }
+}
+
+fn run(command: Command, options: &Options) -> Result<(), Error> {
+    let configs = || configs(options);
+    match command {
+        Command::Help(topic) => {
+            write!(io::stdout().lock(), "{}", cli::help(topic.as_deref())?).map_err(output_error)?
+        }
+        Command::Version => print_line(format_args!(
+            "{} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ))?,
+        Command::Query(content) => query::query(&configs()?, &content).map_err(output_error)?,
+        Command::QueryBatch => query::query_batch(&configs()?).map_err(output_error)?,
+        Command::Execute(id) => execute::execute(&id, false, &configs()?)
+            .map_err(|error| Error::execution(error.to_string()))?,
+        Command::Reveal(id) => execute::execute(&id, true, &configs()?)
+            .map_err(|error| Error::execution(error.to_string()))?,
+        Command::Serve => daemon::serve(configs()?, loader(options)),
+        Command::Listen(socket) => daemon::listen(configs()?, loader(options), socket)
+            .map_err(|error| Error::execution(error.to_string()))?,
+        Command::CheckConfig => check_config(options)?,
+        Command::Schema => print_line(
+            serde_json::to_string_pretty(&Configs::schema())
+                .map_err(|error| Error::execution(error.to_string()))?,
+        )?,
+        Command::Describe(profile) => describe(profile)?,
+        Command::Migrate(file, write, force) => migrate(file, write, force)?,
+        Command::Edit(edit, force) => {
+            // The last file given with --settings takes precedence, so it is the one edited
+            let file = options
+                .settings
+                .last()
+                .cloned()
+                .unwrap_or_else(Loader::user_file);
+            edit_file(&file, &loader(options), &edit, force).map_err(|error| {
+                Error::config(format!("Failed to edit {}: {}", file.display(), error))
+            })?;
+        }
+    };
+    Ok(())
+}
+
+/// Every settings layer, with the files given on the command line on top
+fn loader(options: &Options) -> Loader {
+    options
+        .settings
+        .iter()
+        .fold(Loader::standard(), |loader, path| loader.file(path))
+}
+
+/// Load the configs, printing settings skipped on stderr
+fn configs(options: &Options) -> Result<Configs, Error> {
+    let configs = loader(options)
+        .load()
+        .map_err(|error| Error::config(format!("Settings is invalid: {}", error)))?;
+    for warning in configs.get_warnings() {
+        if let Ok(warning) = serde_json::to_string(warning) {
+            eprintln!("{}", warning);
+        }
+    }
+    Ok(configs)
+}
+
+fn check_config(options: &Options) -> Result<(), Error> {
+    let document = loader(options)
+        .merged()
+        .map_err(|error| Error::config(format!("Settings is invalid: {}", error)))?;
+    let diagnostics = diagnose(&document);
+    for diagnostic in &diagnostics {
+        match serde_json::to_string(diagnostic) {
+            Ok(diagnostic) => print_line(diagnostic)?,
+            Err(error) => eprintln!("Failed to serialize: {}", error),
+        }
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        Err(Error::config(format!(
+            "{} errors are found in settings",
+            errors
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Print the manifest of the profile, by default the one next to the executable.
+/// Problems found are printed on stderr, one JSON diagnostic per line.
+fn describe(profile: Option<PathBuf>) -> Result<(), Error> {
+    let profile = match profile {
+        Some(profile) => profile,
+        None => default_profile()?,
+    };
+    let (manifest, diagnostics) = match Manifest::load(&profile, cli::actions()) {
+        Ok((manifest, diagnostics)) => (Some(manifest), diagnostics),
+        Err(diagnostics) => (None, diagnostics),
     };
+    for diagnostic in &diagnostics {
+        if let Ok(diagnostic) = serde_json::to_string(diagnostic) {
+            eprintln!("{}", diagnostic);
+        }
+    }
+    let manifest =
+        manifest.ok_or_else(|| Error::config(format!("{} is invalid", profile.display())))?;
+    print_line(
+        serde_json::to_string(&manifest).map_err(|error| Error::execution(error.to_string()))?,
+    )
+}
+
+/// Profile next to the executable, or else the one of the source tree it is built from,
+/// as for `cargo run`, or else the one in the working directory
+fn default_profile() -> Result<PathBuf, Error> {
+    let installed = std::env::current_exe()
+        .map_err(|error| Error::execution(error.to_string()))?
+        .with_file_name(PROFILE_NAME);
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROFILE_NAME);
+    let profile = [installed.as_path(), &source, Path::new(PROFILE_NAME)]
+        .iter()
+        .find(|profile| profile.is_file())
+        .map(|profile| profile.to_path_buf());
+    // The installed one is reported missing if none is found
+    Ok(profile.unwrap_or(installed))
+}
+
+/// Upgrade the settings file, by default the user one, printing one JSON change per line
+fn migrate(file: Option<PathBuf>, write: bool, force: bool) -> Result<(), Error> {
+    let file = file.unwrap_or_else(Loader::user_file);
+    let changes = migrate_file(&file, write, force).map_err(|error| {
+        Error::config(format!("Failed to migrate {}: {}", file.display(), error))
+    })?;
+    for change in &changes {
+        match serde_json::to_string(change) {
+            Ok(change) => print_line(change)?,
+            Err(error) => eprintln!("Failed to serialize: {}", error),
+        }
+    }
+    Ok(())
+}
+
+/// Print a line on stdout
+fn print_line<D: Display>(line: D) -> Result<(), Error> {
+    writeln!(io::stdout().lock(), "{}", line).map_err(output_error)
+}
+
+/// Error of the output. Once its reader is gone, e.g. `check-config | head`,
+/// nobody is left to tell, so the program ends quietly.
+fn output_error(error: io::Error) -> Error {
+    if error.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    Error::execution(error.to_string())
 }
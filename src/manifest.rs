@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::configurator::{Diagnostic, Severity};
+use crate::utils::serde::deserialize_from_bytes;
+
+/// Version of the JSON records printed for services, bumped on every incompatible change
+pub const OUTPUT_VERSION: u32 = 1;
+
+/// Name of the extension profile, looked up next to the executable
+pub const PROFILE_NAME: &str = "profile.yaml";
+
+/// Keys of the profile
+const FIELDS: &[&str] = &["title", "subtitle", "keyword", "icon"];
+
+/// Extension as registered by the host, normalized from `profile.yaml`
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub title: String,
+    pub subtitle: String,
+    /// Keyword the host triggers the extension with, empty if queried without one
+    pub keyword: String,
+    /// Absolute path to the icon, resolved against the folder of the profile
+    pub icon: PathBuf,
+    /// Commands the host invokes the binary with
+    pub actions: Vec<String>,
+    pub output_version: u32,
+}
+
+impl Manifest {
+    /// Load and validate the profile. Warnings are returned along with the manifest,
+    /// and every problem is returned instead if any of them is an error.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        actions: Vec<String>,
+    ) -> Result<(Manifest, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let path = path.as_ref();
+        let profile: Value = fs::read(path)
+            .and_then(|bytes| deserialize_from_bytes(&bytes))
+            .map_err(|error| {
+                vec![Diagnostic::new(
+                    Severity::Error,
+                    "",
+                    format!("Failed to read {}: {}", path.display(), error),
+                )]
+            })?;
+        let folder = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::from_value(&profile, folder, actions)
+    }
+
+    /// Validate the profile document, resolving the icon against `folder`
+    pub fn from_value(
+        profile: &Value,
+        folder: &Path,
+        actions: Vec<String>,
+    ) -> Result<(Manifest, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let fields = match profile.as_object() {
+            Some(fields) => fields,
+            None => {
+                return Err(vec![Diagnostic::new(
+                    Severity::Error,
+                    "",
+                    "Profile must be a mapping",
+                )])
+            }
+        };
+        for key in fields.keys() {
+            if !FIELDS.contains(&key.as_str()) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    key.as_str(),
+                    "Unknown key is ignored",
+                ));
+            }
+        }
+        let mut field =
+            |name: &str, allow_empty: bool| string(fields, name, allow_empty, &mut diagnostics);
+        let title = field("title", false);
+        let subtitle = field("subtitle", false);
+        let keyword = field("keyword", true);
+        let icon = field("icon", false);
+        let icon = folder.join(icon);
+        let icon_given = !diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.key == "icon");
+        // The icon is the host's to show, which may go without it
+        if icon_given && !icon.is_file() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "icon",
+                format!("{} is not a file", icon.display()),
+            ));
+        }
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            return Err(diagnostics);
+        }
+        let manifest = Manifest {
+            title,
+            subtitle,
+            keyword,
+            icon: icon.canonicalize().unwrap_or(icon),
+            actions,
+            output_version: OUTPUT_VERSION,
+        };
+        Ok((manifest, diagnostics))
+    }
+}
+
+/// Trimmed string value of the field, or an empty string along with the problem found
+fn string(
+    fields: &Map<String, Value>,
+    name: &str,
+    allow_empty: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> String {
+    let problem = match fields.get(name) {
+        Some(Value::String(value)) if allow_empty || !value.trim().is_empty() => {
+            return value.trim().to_owned()
+        }
+        Some(Value::String(_)) => "Value is empty",
+        Some(_) => "Value must be a string",
+        None => "Value is missing",
+    };
+    diagnostics.push(Diagnostic::new(Severity::Error, name, problem));
+    String::new()
+}
+
+#[cfg(test)]
+mod manifest_test {
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::configurator::Severity;
+    use crate::manifest::{Manifest, OUTPUT_VERSION};
+
+    #[test]
+    fn test_load() {
+        let folder = std::env::temp_dir().join(format!("manifest-{}", std::process::id()));
+        fs::create_dir_all(&folder).expect("Failed to create");
+        fs::write(folder.join("icon.png"), b"").expect("Failed to write");
+        let profile = folder.join("profile.yaml");
+        let content = "title: Launch Service\nsubtitle: Launcher\nkeyword: \"\"\nicon: icon.png\n";
+        fs::write(&profile, content).expect("Failed to write");
+        let (manifest, warnings) =
+            Manifest::load(&profile, vec!["query".to_owned()]).expect("Failed to load");
+        assert!(warnings.is_empty());
+        assert_eq!(manifest.title, "Launch Service");
+        assert_eq!(manifest.keyword, "");
+        assert!(manifest.icon.is_absolute());
+        assert_eq!(manifest.actions, vec!["query"]);
+        let serialized = serde_json::to_value(&manifest).expect("Failed to serialize");
+        assert_eq!(serialized["outputVersion"], json!(OUTPUT_VERSION));
+        fs::remove_dir_all(folder).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_load_shipped() {
+        let profile = concat!(env!("CARGO_MANIFEST_DIR"), "/profile.yaml");
+        let (manifest, diagnostics) =
+            Manifest::load(profile, Vec::new()).expect("Failed to load profile.yaml");
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.key == "icon"));
+        assert_eq!(manifest.title, "Launch Service");
+        assert!(manifest.icon.ends_with("icon.png"));
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let profile = json!({"title": " ", "subtitle": 1, "icon": "missing.png", "author": "me"});
+        let diagnostics = Manifest::from_value(&profile, &std::env::temp_dir(), Vec::new())
+            .expect_err("Invalid profile is accepted");
+        let mut problems = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.key.as_str()))
+            .collect::<Vec<_>>();
+        problems.sort();
+        let expected = vec![
+            (Severity::Warning, "author"),
+            (Severity::Warning, "icon"),
+            (Severity::Error, "keyword"),
+            (Severity::Error, "subtitle"),
+            (Severity::Error, "title"),
+        ];
+        assert_eq!(problems, expected);
+    }
+
+    #[test]
+    fn test_not_mapping() {
+        let diagnostics =
+            Manifest::from_value(&json!(["title"]), &std::env::temp_dir(), Vec::new())
+                .expect_err("List is accepted");
+        assert_eq!(diagnostics.len(), 1);
+    }
+}
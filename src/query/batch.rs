@@ -0,0 +1,71 @@
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+
+use crate::query::catalog::Catalog;
+use crate::query::service::Service;
+use crate::utils::serde::serialize_to_string;
+
+/// A matched service, tagged with the query it belongs to
+#[derive(Serialize)]
+struct Tagged<'a> {
+    query: &'a str,
+    #[serde(flatten)]
+    service: &'a Service,
+}
+
+/// Answer one query per input line from the same catalog, writing every match tagged with its query
+pub fn query_batch<R: BufRead, W: Write>(
+    catalog: &Catalog,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let query = line.trim_end_matches('\r');
+        if query.is_empty() {
+            continue;
+        }
+        for service in catalog.query(query) {
+            match serialize_to_string(&Tagged { query, service }) {
+                Ok(service) => writeln!(output, "{}", service)?,
+                Err(error) => eprintln!("Failed to serialize: {}", error),
+            }
+        }
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod batch_test {
+    use crate::configurator::get_configs;
+    use crate::query::batch::query_batch;
+    use crate::query::catalog::Catalog;
+    use crate::query::kind::Kind;
+    use crate::query::service::Service;
+
+    #[test]
+    fn test_query_batch() {
+        let configs = get_configs();
+        let catalog: Catalog = vec![
+            Service::new(
+                "/System/Applications/Books.app",
+                Kind::Application,
+                &configs,
+            ),
+            Service::new(
+                "/System/Applications/Safari.app",
+                Kind::Application,
+                &configs,
+            ),
+        ]
+        .into();
+        let mut output = Vec::new();
+        query_batch(&catalog, "saf\n\nx\nbo\n".as_bytes(), &mut output).expect("Failed to query");
+        let string = String::from_utf8(output).expect("Failed to parse");
+        let expected = r#"{"query":"saf","title":"Safari","subtitle":"/System/Applications/Safari.app","id":"/System/Applications/Safari.app","kind":"application"}
+{"query":"bo","title":"Books","subtitle":"/System/Applications/Books.app","id":"/System/Applications/Books.app","kind":"application"}
+"#;
+        assert_eq!(string, expected);
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::Path;
+
+/// Bundle identifier, `CFBundleIdentifier` in `Contents/Info.plist` of the bundle
+pub fn identifier(path: &Path) -> Option<String> {
+    let info = plist::Value::from_file(path.join("Contents/Info.plist")).ok()?;
+    info.as_dictionary()?
+        .get("CFBundleIdentifier")?
+        .as_string()
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod bundle_test {
+    use std::fs;
+
+    use crate::query::bundle::identifier;
+
+    #[test]
+    fn test_identifier() {
+        let bundle = std::env::temp_dir().join(format!("bundle-{}.app", std::process::id()));
+        fs::create_dir_all(bundle.join("Contents")).expect("Failed to create");
+        assert_eq!(identifier(&bundle), None);
+        let info = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.example.Tool</string>
+</dict>
+</plist>"#;
+        fs::write(bundle.join("Contents/Info.plist"), info).expect("Failed to write");
+        assert_eq!(identifier(&bundle).as_deref(), Some("com.example.Tool"));
+        fs::remove_dir_all(bundle).expect("Failed to clean up");
+    }
+}
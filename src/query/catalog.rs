@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use crate::configurator::Configs;
+use crate::query::desktop::Exclusion;
+use crate::query::service::Service;
+use crate::query::walker::{self, Visited};
+
+/// Every bundle under the configured paths, collected once and queried many times
+pub struct Catalog {
+    services: Vec<Service>,
+    /// Desktop entries walked but not listed, with the reason
+    excluded: Vec<(PathBuf, Exclusion)>,
+}
+
+impl Catalog {
+    /// Walk through all configured paths and remember the bundles found
+    pub fn new(configs: &Configs) -> Self {
+        let mut services = Vec::new();
+        let mut visited = Visited::default();
+        for root in configs.get_roots() {
+            walker::walk_root(configs, root, &mut visited, &mut |path, kind, entry| {
+                let service =
+                    Service::with_entry(path, kind, entry, configs).with_label(root.label.clone());
+                let actions = entry
+                    .map(|entry| service.actions(entry, configs))
+                    .unwrap_or_default();
+                services.push(service);
+                services.extend(actions);
+            });
+        }
+        Catalog {
+            services,
+            excluded: visited.into_excluded(),
+        }
+    }
+
+    /// Every service found
+    pub fn services(&self) -> &[Service] {
+        &self.services
+    }
+
+    /// Desktop entries walked but not listed, with the reason
+    pub fn excluded(&self) -> &[(PathBuf, Exclusion)] {
+        &self.excluded
+    }
+
+    /// Services matching the request, in the order they were found
+    pub fn query<'a>(&'a self, req: &str) -> impl Iterator<Item = &'a Service> + 'a {
+        let req = req.to_lowercase();
+        self.services
+            .iter()
+            .filter(move |service| service.matches(&req))
+    }
+}
+
+impl From<Vec<Service>> for Catalog {
+    fn from(services: Vec<Service>) -> Self {
+        Catalog {
+            services,
+            excluded: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod catalog_test {
+    use std::fs;
+
+    use crate::configurator::{get_configs, Configs};
+    use crate::query::catalog::Catalog;
+    use crate::query::kind::Kind;
+    use crate::query::service::Service;
+
+    fn catalog() -> Catalog {
+        let configs = get_configs();
+        vec![
+            Service::new(
+                "/System/Applications/Books.app",
+                Kind::Application,
+                &configs,
+            ),
+            Service::new(
+                "/System/Applications/Safari.app",
+                Kind::Application,
+                &configs,
+            ),
+            Service::new(
+                "/System/Applications/Activity Monitor.app",
+                Kind::Application,
+                &configs,
+            ),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn test_query() {
+        let catalog = catalog();
+        let titles = catalog
+            .query("Saf")
+            .map(|service| service.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Safari"]);
+    }
+
+    #[test]
+    fn test_query_reuse() {
+        let catalog = catalog();
+        assert_eq!(catalog.query("am").count(), 1);
+        assert_eq!(catalog.query("b").count(), 1);
+        assert_eq!(catalog.query("x").count(), 0);
+    }
+
+    #[test]
+    fn test_new() {
+        let root = std::env::temp_dir().join(format!("catalog-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("Utilities/Terminal.app")).expect("Failed to create");
+        fs::create_dir_all(root.join("Safari.app")).expect("Failed to create");
+        let settings = format!(
+            r#"{{"configurable":{{"ignorePaths":{{"value":[]}}}},"internal":{{"paths":[{:?}],"prefNames":{{}}}}}}"#,
+            root
+        );
+        let configs = Configs::from(settings).expect("Failed to parse");
+        let catalog = Catalog::new(&configs);
+        assert_eq!(catalog.query("").count(), 2);
+        assert_eq!(catalog.query("ter").count(), 1);
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_new_root_options() {
+        let root = std::env::temp_dir().join(format!("catalog-root-{}", std::process::id()));
+        fs::create_dir_all(root.join("Utilities/Terminal.app")).expect("Failed to create");
+        fs::create_dir_all(root.join("Safari.app")).expect("Failed to create");
+        let other = root.join("Utilities");
+        let settings = format!(
+            r#"{{"internal":{{"paths":[{:?},{{"path":{:?},"depth":1,"label":"Top","priority":1}}],"prefNames":{{}}}}}}"#,
+            other, root
+        );
+        let configs = Configs::from(settings).expect("Failed to parse");
+        let catalog = Catalog::new(&configs);
+        let services = catalog.services();
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].title, "Safari");
+        assert_eq!(services[0].label(), Some("Top"));
+        assert_eq!(services[1].title, "Terminal");
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+}
@@ -1,47 +1,92 @@
-use std::collections::HashSet;
-use std::ffi::{OsStr, OsString};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
 
+use crate::configurator::Root;
+use crate::query::filter::{Condition, Filter, DEFAULT_FILTERS};
+use crate::query::ignore::IgnoreRules;
+use crate::query::kind::Kind;
+
+#[allow(clippy::enum_variant_names)]
 pub enum Outcome {
     UnwantedPath,
-    BundlePath,
+    BundlePath(Kind),
     NormalPath,
 }
 
 pub struct Checker<'a> {
-    ignored_paths: &'a HashSet<PathBuf>,
-    bundle_extensions: HashSet<OsString>,
+    /// Filters applied in order, the first one whose condition holds deciding
+    filters: &'a [Filter],
+    ignore_rules: &'a IgnoreRules,
+    bundle_kinds: &'a HashMap<String, Kind>,
+    /// Extensions listed under the root, every extension of `bundle_kinds` if `None`
+    extensions: Option<&'a [String]>,
+    follow_symlinks: bool,
+    /// Folder of the root walked, which desktop-file IDs are relative to
+    root: Option<&'a Path>,
 }
 
 impl<'a> Checker<'a> {
-    pub fn new(ignored_paths: &'a HashSet<PathBuf>) -> Self {
-        let bundle_extensions: HashSet<OsString> = vec!["app", "prefPane"]
-            .into_iter()
-            .map(OsString::from)
-            .collect();
+    pub fn new(ignore_rules: &'a IgnoreRules, bundle_kinds: &'a HashMap<String, Kind>) -> Self {
         Checker {
-            ignored_paths,
-            bundle_extensions,
+            filters: DEFAULT_FILTERS,
+            ignore_rules,
+            bundle_kinds,
+            extensions: None,
+            follow_symlinks: false,
+            root: None,
         }
     }
 
+    /// Checker applying the walk options of the root
+    pub fn with_root(mut self, root: &'a Root) -> Self {
+        self.extensions = root.extensions.as_deref();
+        self.follow_symlinks = root.follow_symlinks;
+        self.root = Some(&root.path);
+        self
+    }
+
+    /// Folder of the root walked, if any
+    pub fn root(&self) -> Option<&Path> {
+        self.root
+    }
+
+    /// Checker applying the filters instead of the built-in ones
+    pub fn with_filters(mut self, filters: &'a [Filter]) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Outcome of the first filter whose condition holds, walking into the path if none does
     pub fn check<P: AsRef<Path>>(&self, path: P) -> Outcome {
-        if self.is_symlink(path.as_ref())
-            || self.is_hidden(path.as_ref())
-            || self.is_ignored_path(path.as_ref())
-        {
-            Outcome::UnwantedPath
-        } else if self.is_bundle(path.as_ref()) {
-            Outcome::BundlePath
-        } else {
-            Outcome::NormalPath
+        let path = path.as_ref();
+        let kind = self.bundle_kind(path);
+        self.filters
+            .iter()
+            .find_map(|filter| filter.outcome(self.matches(&filter.condition, path), kind.as_ref()))
+            .unwrap_or(Outcome::NormalPath)
+    }
+
+    fn matches(&self, condition: &Condition, path: &Path) -> bool {
+        match condition {
+            Condition::Hidden => self.is_hidden(path),
+            Condition::Symlink => self.is_unwanted_symlink(path),
+            Condition::Ignore => self.is_ignored_path(path),
+            Condition::Bundle => self.bundle_kind(path).is_some(),
+            condition => condition.matches(path).unwrap_or(false),
         }
     }
 
-    fn is_bundle(&self, path: &Path) -> bool {
-        path.extension()
-            .map(|ext| self.bundle_extensions.contains(ext))
-            .unwrap_or(false)
+    /// Kind of the bundle, or `None` if the path is not a bundle
+    fn bundle_kind(&self, path: &Path) -> Option<Kind> {
+        let extension = path.extension()?.to_str()?;
+        if let Some(extensions) = self.extensions {
+            if !extensions.iter().any(|listed| listed == extension) {
+                return None;
+            }
+        }
+        self.bundle_kinds.get(extension).cloned()
     }
 
     /// Checker that checks if a path is hidden by checking its prefix dot
@@ -53,132 +98,211 @@ impl<'a> Checker<'a> {
     }
 
     fn is_symlink(&self, path: &Path) -> bool {
-        let path: &std::path::Path = path.into();
         path.symlink_metadata()
             .map(|metadata| metadata.file_type())
             .map(|file_type| file_type.is_symlink())
             .unwrap_or(false)
     }
 
+    /// Symlinks are unwanted unless followed. When they are, a path is also unwanted
+    /// if it is a broken link, or if its real path, through any link above it, is ignored.
+    fn is_unwanted_symlink(&self, path: &Path) -> bool {
+        if !self.follow_symlinks {
+            return self.is_symlink(path);
+        }
+        match fs::canonicalize(path) {
+            Ok(target) => target != path && self.is_ignored_path(&target),
+            Err(_) => self.is_symlink(path),
+        }
+    }
+
     fn is_ignored_path(&self, path: &Path) -> bool {
-        self.ignored_paths.contains(path)
+        self.ignore_rules.is_ignored(path)
+    }
+}
+
+#[cfg(test)]
+impl Checker<'static> {
+    /// Checker with the default bundle kinds, ignoring the patterns
+    fn ignoring(patterns: &[&str]) -> Self {
+        let ignore_rules = Box::leak(Box::new(IgnoreRules::new(patterns)));
+        Checker::new(ignore_rules, Box::leak(Box::new(Kind::defaults())))
     }
 }
 
 #[cfg(test)]
 mod bundle_checker_test {
-    use std::collections::HashSet;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
+    use crate::configurator::Root;
     use crate::query::checker::Checker;
+    use crate::query::ignore::IgnoreRules;
+    use crate::query::kind::Kind;
 
     #[test]
     fn test_is_bundle_app() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert!(checker.is_bundle(Path::new("/System/Applications/Books.app")));
+        let checker = Checker::ignoring(&[]);
+        let kind = checker.bundle_kind(Path::new("/System/Applications/Books.app"));
+        assert_eq!(kind, Some(Kind::Application));
     }
 
     #[test]
     fn test_is_bundle_pref() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert!(checker.is_bundle(Path::new(
-            "/System/Library/PreferencePanes/Network.prefPane"
-        )));
+        let checker = Checker::ignoring(&[]);
+        let kind = checker.bundle_kind(Path::new(
+            "/System/Library/PreferencePanes/Network.prefPane",
+        ));
+        assert_eq!(kind, Some(Kind::PreferencePane));
     }
 
     #[test]
     fn test_is_bundle_folder() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert_eq!(checker.is_bundle(Path::new("/Applications")), false);
+        let checker = Checker::ignoring(&[]);
+        assert!(checker.bundle_kind(Path::new("/Applications")).is_none());
+    }
+
+    #[test]
+    fn test_is_bundle_root_extensions() {
+        let mut root = Root::from(PathBuf::from("/Applications"));
+        root.extensions = Some(vec!["prefPane".to_owned(), "unknown".to_owned()]);
+        let checker = Checker::ignoring(&[]).with_root(&root);
+        assert!(checker
+            .bundle_kind(Path::new("/Applications/Books.app"))
+            .is_none());
+        assert!(checker
+            .bundle_kind(Path::new("/Applications/A.unknown"))
+            .is_none());
+        let kind = checker.bundle_kind(Path::new("/Applications/Network.prefPane"));
+        assert_eq!(kind, Some(Kind::PreferencePane));
     }
 
     #[test]
     fn test_is_bundle_file() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert_eq!(checker.is_bundle(Path::new("/dev/null")), false);
+        let checker = Checker::ignoring(&[]);
+        assert!(checker.bundle_kind(Path::new("/dev/null")).is_none());
+    }
+
+    #[test]
+    fn test_is_bundle_kinds() {
+        let ignore_rules = IgnoreRules::default();
+        let bundle_kinds = vec![
+            ("saver".to_owned(), Kind::ScreenSaver),
+            (
+                "sketch".to_owned(),
+                Kind::Custom("sketchDocument".to_owned()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let checker = Checker::new(&ignore_rules, &bundle_kinds);
+        let kind = checker.bundle_kind(Path::new("/Library/Screen Savers/Flurry.saver"));
+        assert_eq!(kind, Some(Kind::ScreenSaver));
+        let kind = checker.bundle_kind(Path::new("/Users/me/Logo.sketch"));
+        assert_eq!(kind, Some(Kind::Custom("sketchDocument".to_owned())));
+        assert!(checker
+            .bundle_kind(Path::new("/Users/me/Books.app"))
+            .is_none());
     }
 }
 
 #[cfg(test)]
 mod hidden_checker_test {
-    use std::collections::HashSet;
     use std::path::Path;
 
     use crate::query::checker::Checker;
 
     #[test]
     fn test_is_hidden() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
+        let checker = Checker::ignoring(&[]);
         assert!(checker.is_hidden(Path::new(".test")));
     }
 
     #[test]
     fn test_is_not_hidden() {
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert_eq!(checker.is_hidden(Path::new("test/test")), false);
+        let checker = Checker::ignoring(&[]);
+        assert!(!checker.is_hidden(Path::new("test/test")));
     }
 }
 
 #[cfg(test)]
 mod symlink_test {
-    use std::collections::HashSet;
     use std::path::PathBuf;
 
     use crate::query::checker::Checker;
 
-    const SYMLINK_PATH: &'static str = "/System/Library/PreferencePanes/PrintAndFax.prefPane";
-    const APP_PATH: &'static str = "/System/Applications/Books.app";
+    const SYMLINK_PATH: &str = "/System/Library/PreferencePanes/PrintAndFax.prefPane";
+    const APP_PATH: &str = "/System/Applications/Books.app";
 
     #[test]
     fn test_is_legit() {
         let path = PathBuf::from(SYMLINK_PATH);
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
+        let checker = Checker::ignoring(&[]);
         assert!(checker.is_symlink(&path))
     }
 
     #[test]
     fn test_is_not_legit() {
         let path = PathBuf::from(APP_PATH);
-        let ignored_paths = HashSet::new();
-        let checker = Checker::new(&ignored_paths);
-        assert_eq!(checker.is_symlink(&path), false);
+        let checker = Checker::ignoring(&[]);
+        assert!(!checker.is_symlink(&path));
     }
 }
 
 #[cfg(test)]
 mod ignore_checker_test {
-    use std::collections::HashSet;
-    use std::path::{Path, PathBuf};
+    use std::path::Path;
 
     use crate::query::checker::Checker;
 
     #[test]
     fn test_is_legit() {
-        let ignored_paths: HashSet<PathBuf> = vec!["/Users/cheng", "/usr/bin"]
-            .into_iter()
-            .map(PathBuf::from)
-            .collect();
-        let checker = Checker::new(&ignored_paths);
+        let checker = Checker::ignoring(&["/Users/cheng", "/usr/bin"]);
         assert!(checker.is_ignored_path(Path::new("/Users/cheng")))
     }
 
+    #[test]
+    fn test_is_legit_subtree() {
+        let checker = Checker::ignoring(&["/Users/cheng", "/usr/bin"]);
+        assert!(checker.is_ignored_path(Path::new("/Users/cheng/Applications")))
+    }
+
     #[test]
     fn test_is_not_legit() {
-        let ignored_paths: HashSet<PathBuf> = vec!["/Users/cheng", "/usr/bin"]
-            .into_iter()
-            .map(PathBuf::from)
-            .collect();
-        let checker = Checker::new(&ignored_paths);
-        assert_eq!(
-            checker.is_ignored_path(Path::new("/Users/cheng/Applications")),
-            false
-        )
+        let checker = Checker::ignoring(&["/Users/cheng", "/usr/bin"]);
+        assert!(!checker.is_ignored_path(Path::new("/Users/chengx/Applications")))
+    }
+}
+
+#[cfg(test)]
+mod filter_checker_test {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::query::checker::{Checker, Outcome};
+    use crate::query::filter::Filter;
+    use crate::query::kind::Kind;
+
+    #[test]
+    fn test_filters_in_order() {
+        let mut filters = vec![serde_json::from_value::<Filter>(json!({
+            "rule": "name", "pattern": "Uninstaller", "action": "prune"
+        }))
+        .expect("Failed to parse")];
+        filters.extend(Filter::defaults());
+        let checker = Checker::ignoring(&[]).with_filters(&filters);
+        let uninstaller = checker.check(Path::new("/Applications/Adobe Uninstaller.app"));
+        assert!(matches!(uninstaller, Outcome::UnwantedPath));
+        let editor = checker.check(Path::new("/Applications/Editor.app"));
+        assert!(matches!(editor, Outcome::BundlePath(Kind::Application)));
+        let folder = checker.check(Path::new("/Applications/Utilities"));
+        assert!(matches!(folder, Outcome::NormalPath));
+    }
+
+    #[test]
+    fn test_filters_empty() {
+        let checker = Checker::ignoring(&[]).with_filters(&[]);
+        let outcome = checker.check(Path::new("/Applications/Editor.app"));
+        assert!(matches!(outcome, Outcome::NormalPath));
     }
 }
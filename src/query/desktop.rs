@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Group of a desktop file holding the entry itself
+const GROUP: &str = "[Desktop Entry]";
+/// Start of the groups holding the actions, followed by the action identifier
+const ACTION_GROUP: &str = "[Desktop Action ";
+/// Separator of the desktop file and the action in the identifier of an action
+const ACTION_SEPARATOR: char = '#';
+
+/// Desktop Entry as specified by freedesktop.org, with the keys of its `[Desktop Entry]` group
+#[derive(Debug, Default, PartialEq)]
+pub struct DesktopEntry {
+    fields: HashMap<String, String>,
+    /// Keys of the `[Desktop Action <action>]` groups, keyed by action
+    actions: HashMap<String, DesktopEntry>,
+}
+
+/// Group of the desktop file the lines parsed belong to
+enum Group {
+    Entry,
+    Action(String),
+    Other,
+}
+
+impl DesktopEntry {
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::read_to_string(path).map(|content| Self::parse(&content))
+    }
+
+    /// Parse the `[Desktop Entry]` group and the action groups,
+    /// ignoring comments, other groups and malformed lines
+    pub fn parse(content: &str) -> Self {
+        let mut entry = DesktopEntry::default();
+        let mut group = Group::Other;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                let action = line
+                    .strip_prefix(ACTION_GROUP)
+                    .and_then(|action| action.strip_suffix(']'));
+                group = match action {
+                    _ if line == GROUP => Group::Entry,
+                    Some(action) => Group::Action(action.to_owned()),
+                    None => Group::Other,
+                };
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let fields = match &group {
+                Group::Entry => &mut entry.fields,
+                Group::Action(action) => {
+                    &mut entry.actions.entry(action.to_owned()).or_default().fields
+                }
+                Group::Other => continue,
+            };
+            fields
+                .entry(key.trim().to_owned())
+                .or_insert_with(|| unescape(value.trim()));
+        }
+        entry
+    }
+
+    /// Actions listed in `Actions` having a group with a `Name`, in order
+    pub fn actions(&self) -> Vec<(&str, &DesktopEntry)> {
+        self.list("Actions")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|action| self.actions.get_key_value(action))
+            .filter(|(_, entry)| entry.get("Name").is_some())
+            .map(|(action, entry)| (action.as_str(), entry))
+            .collect()
+    }
+
+    /// Group of the action, if listed in `Actions`
+    pub fn action(&self, action: &str) -> Option<&DesktopEntry> {
+        self.actions()
+            .into_iter()
+            .find(|(listed, _)| *listed == action)
+            .map(|(_, entry)| entry)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    /// Value of a localized key in the first locale of the chain having one, e.g. `Name[de_CH]`,
+    /// then `Name[de]`, falling back to the unlocalized `Name`
+    pub fn localized(&self, key: &str, locales: &[String]) -> Option<&str> {
+        locales
+            .iter()
+            .find_map(|locale| self.get(&format!("{}[{}]", key, locale)))
+            .or_else(|| self.get(key))
+    }
+
+    fn is_true(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    /// Items of a list value, separated by `;`
+    fn list(&self, key: &str) -> Option<Vec<&str>> {
+        let value = self.get(key)?;
+        Some(value.split(';').filter(|item| !item.is_empty()).collect())
+    }
+
+    /// Why the entry is not shown in the menus of the environment, or `None` if it is
+    pub fn exclusion(&self, environment: &Environment) -> Option<Exclusion> {
+        let kind = self.get("Type").unwrap_or_default();
+        if kind != "Application" {
+            return Some(Exclusion::NotApplication(kind.to_owned()));
+        }
+        if self.is_true("NoDisplay") {
+            return Some(Exclusion::NoDisplay);
+        }
+        if self.is_true("Hidden") {
+            return Some(Exclusion::Hidden);
+        }
+        if let Some(exclusion) = self.show_in(&environment.desktops) {
+            return Some(exclusion);
+        }
+        let try_exec = self.get("TryExec").filter(|program| !program.is_empty())?;
+        if environment.find_program(try_exec) {
+            None
+        } else {
+            Some(Exclusion::TryExec(try_exec.to_owned()))
+        }
+    }
+
+    /// Check `OnlyShowIn` and `NotShowIn` against the current desktops, the first one listed deciding
+    fn show_in(&self, desktops: &[String]) -> Option<Exclusion> {
+        let only = self.list("OnlyShowIn");
+        let not = self.list("NotShowIn").unwrap_or_default();
+        for desktop in desktops {
+            if only
+                .as_ref()
+                .is_some_and(|only| only.contains(&desktop.as_str()))
+            {
+                return None;
+            }
+            if not.contains(&desktop.as_str()) {
+                return Some(Exclusion::NotShowIn(desktop.to_owned()));
+            }
+        }
+        let only = only?;
+        Some(Exclusion::OnlyShowIn(only.join(";")))
+    }
+}
+
+/// Desktop environment entries are shown in
+#[derive(Debug, Default, Clone)]
+pub struct Environment {
+    /// Current desktops, from `XDG_CURRENT_DESKTOP`
+    desktops: Vec<String>,
+    /// Folders searched for programs, from `PATH`
+    path: Option<OsString>,
+}
+
+impl Environment {
+    pub fn current() -> Self {
+        let desktops = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        Environment::new(&desktops, std::env::var_os("PATH"))
+    }
+
+    /// Environment of the `:` separated desktops, searching programs in `path`
+    pub fn new(desktops: &str, path: Option<OsString>) -> Self {
+        let desktops = desktops
+            .split(':')
+            .filter(|desktop| !desktop.is_empty())
+            .map(String::from)
+            .collect();
+        Environment { desktops, path }
+    }
+
+    /// Whether the program, a path or a name looked up in `PATH`, is an executable file
+    fn find_program(&self, program: &str) -> bool {
+        if program.contains('/') {
+            return is_executable(Path::new(program));
+        }
+        self.path.as_ref().is_some_and(|path| {
+            std::env::split_paths(path).any(|folder| is_executable(&folder.join(program)))
+        })
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Why a desktop entry is not listed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exclusion {
+    /// The file cannot be read
+    Unreadable(String),
+    /// `Type` is not `Application`
+    NotApplication(String),
+    NoDisplay,
+    Hidden,
+    /// None of the current desktops is in `OnlyShowIn`
+    OnlyShowIn(String),
+    /// A current desktop is in `NotShowIn`
+    NotShowIn(String),
+    /// The `TryExec` program is not found
+    TryExec(String),
+    /// An earlier entry has the same desktop-file ID
+    Shadowed(String),
+}
+
+impl Display for Exclusion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Exclusion::Unreadable(error) => write!(f, "it cannot be read: {}", error),
+            Exclusion::NotApplication(kind) => write!(f, "Type is {:?}, not Application", kind),
+            Exclusion::NoDisplay => write!(f, "NoDisplay is true"),
+            Exclusion::Hidden => write!(f, "Hidden is true"),
+            Exclusion::OnlyShowIn(desktops) => write!(
+                f,
+                "OnlyShowIn is {}, which has none of XDG_CURRENT_DESKTOP",
+                desktops
+            ),
+            Exclusion::NotShowIn(desktop) => write!(f, "NotShowIn has {}", desktop),
+            Exclusion::TryExec(program) => write!(
+                f,
+                "TryExec {} is not an executable file, nor found on PATH",
+                program
+            ),
+            Exclusion::Shadowed(id) => write!(f, "an earlier entry has the ID {}", id),
+        }
+    }
+}
+
+/// Desktop-file ID: the path relative to the applications folder, with `/` replaced by `-`,
+/// e.g. `kde/konsole.desktop` is `kde-konsole.desktop`
+pub fn desktop_id(folder: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(folder).unwrap_or(path);
+    relative
+        .iter()
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Identifier of an action of the desktop file, e.g. `/usr/share/applications/firefox.desktop#new-window`
+pub fn action_id(path: &Path, action: &str) -> String {
+    format!("{}{}{}", path.display(), ACTION_SEPARATOR, action)
+}
+
+/// Desktop file and action of an action identifier, or `None` if the identifier is not one
+pub fn split_action_id(id: &str) -> Option<(&Path, &str)> {
+    let (path, action) = id.rsplit_once(ACTION_SEPARATOR)?;
+    if path.ends_with(".desktop") && !action.is_empty() {
+        Some((Path::new(path), action))
+    } else {
+        None
+    }
+}
+
+/// Replace the escape sequences of string values: `\s`, `\n`, `\t`, `\r` and `\\`
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(character) = chars.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Arguments of the `Exec` command line of the entry, or of one of its actions,
+/// with its field codes expanded for launching without files or URLs.
+/// `%c` and `%i` stand for the name, localized along `locales`, and the icon of the entry,
+/// and `%k` for `path`, the desktop file.
+pub fn command_line(
+    entry: &DesktopEntry,
+    action: Option<&DesktopEntry>,
+    path: &Path,
+    locales: &[String],
+) -> Option<Vec<String>> {
+    let exec = action.unwrap_or(entry).get("Exec")?;
+    let name = entry.localized("Name", locales).unwrap_or_default();
+    let mut arguments = Vec::new();
+    for argument in split_arguments(exec)? {
+        match argument.as_str() {
+            "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => (),
+            "%i" => {
+                if let Some(icon) = entry.get("Icon") {
+                    arguments.push("--icon".to_owned());
+                    arguments.push(icon.to_owned());
+                }
+            }
+            _ => arguments.push(expand_field_codes(&argument, name, path)),
+        }
+    }
+    if arguments.is_empty() {
+        None
+    } else {
+        Some(arguments)
+    }
+}
+
+/// Split on spaces outside double quotes; inside them, `\` escapes `"`, `` ` ``, `$` and `\`.
+/// Unterminated quotes make the command line invalid.
+fn split_arguments(exec: &str) -> Option<Vec<String>> {
+    let mut arguments = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = exec.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            ' ' => arguments.extend(current.take()),
+            '"' => {
+                let argument = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => argument.push(chars.next()?),
+                        other => argument.push(other),
+                    }
+                }
+            }
+            other => current.get_or_insert_with(String::new).push(other),
+        }
+    }
+    arguments.extend(current);
+    Some(arguments)
+}
+
+/// Expand `%c`, `%k` and `%%` within an argument, dropping the codes standing for files
+fn expand_field_codes(argument: &str, name: &str, path: &Path) -> String {
+    let mut expanded = String::new();
+    let mut chars = argument.chars();
+    while let Some(character) = chars.next() {
+        if character != '%' {
+            expanded.push(character);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(name),
+            Some('k') => expanded.push_str(&path.to_string_lossy()),
+            _ => (),
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod desktop_test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use crate::query::desktop::{
+        action_id, command_line, desktop_id, split_action_id, DesktopEntry, Environment, Exclusion,
+    };
+
+    const FIREFOX: &str = r#"
+# Comment
+[Desktop Entry]
+Type=Application
+Name=Firefox
+Name[de]=Firefox-Webbrowser
+Comment=Browse\sthe web
+Exec=firefox --name "Fire fox" %u
+Icon=firefox
+Actions=new-window;new-private-window;missing;
+
+[Desktop Action new-window]
+Name=New Window
+Exec=firefox --new-window %u
+
+[Desktop Action new-private-window]
+Name=New Private Window
+Name[de]=Neues privates Fenster
+Exec=firefox --private-window %u
+
+[Desktop Action unlisted]
+Name=Unlisted
+Exec=firefox --unlisted
+"#;
+
+    #[test]
+    fn test_parse() {
+        let entry = DesktopEntry::parse(FIREFOX);
+        assert_eq!(entry.get("Name"), Some("Firefox"));
+        assert_eq!(entry.get("Comment"), Some("Browse the web"));
+        assert_eq!(entry.get("Exec"), Some(r#"firefox --name "Fire fox" %u"#));
+        let locales = vec!["de_CH".to_owned(), "de".to_owned(), "en".to_owned()];
+        assert_eq!(
+            entry.localized("Name", &locales),
+            Some("Firefox-Webbrowser")
+        );
+        assert_eq!(entry.localized("Name", &[]), Some("Firefox"));
+        assert_eq!(entry.exclusion(&Environment::default()), None);
+    }
+
+    #[test]
+    fn test_exclusion() {
+        let environment = Environment::default();
+        let hidden = DesktopEntry::parse("[Desktop Entry]\nType=Application\nNoDisplay=true\n");
+        assert_eq!(hidden.exclusion(&environment), Some(Exclusion::NoDisplay));
+        let link = DesktopEntry::parse("[Desktop Entry]\nType=Link\nURL=https://example.com\n");
+        let exclusion = Some(Exclusion::NotApplication("Link".to_owned()));
+        assert_eq!(link.exclusion(&environment), exclusion);
+        let outside = DesktopEntry::parse("Type=Application\n");
+        assert!(outside.exclusion(&environment).is_some());
+    }
+
+    #[test]
+    fn test_exclusion_show_in() {
+        let entry = |keys: &str| {
+            DesktopEntry::parse(&format!("[Desktop Entry]\nType=Application\n{}", keys))
+        };
+        let gnome = Environment::new("ubuntu:GNOME", None);
+        let kde = Environment::new("KDE", None);
+        let only = entry("OnlyShowIn=GNOME;XFCE;\n");
+        assert_eq!(only.exclusion(&gnome), None);
+        let exclusion = Some(Exclusion::OnlyShowIn("GNOME;XFCE".to_owned()));
+        assert_eq!(only.exclusion(&kde), exclusion);
+        assert_eq!(only.exclusion(&Environment::default()), exclusion);
+        let not = entry("NotShowIn=KDE;\n");
+        assert_eq!(not.exclusion(&gnome), None);
+        assert_eq!(
+            not.exclusion(&kde),
+            Some(Exclusion::NotShowIn("KDE".to_owned()))
+        );
+        let first_decides = entry("OnlyShowIn=ubuntu;\nNotShowIn=GNOME;\n");
+        assert_eq!(first_decides.exclusion(&gnome), None);
+    }
+
+    #[test]
+    fn test_exclusion_try_exec() {
+        let folder = std::env::temp_dir().join(format!("desktop-bin-{}", std::process::id()));
+        fs::create_dir_all(&folder).expect("Failed to create");
+        let program = folder.join("tool");
+        fs::write(&program, "#!/bin/sh\n").expect("Failed to write");
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755))
+            .expect("Failed to set mode");
+        fs::write(folder.join("data"), "").expect("Failed to write");
+        let environment = Environment::new("", Some(folder.clone().into_os_string()));
+        let entry = |program: &str| {
+            DesktopEntry::parse(&format!(
+                "[Desktop Entry]\nType=Application\nTryExec={}\n",
+                program
+            ))
+        };
+        assert_eq!(entry("tool").exclusion(&environment), None);
+        assert_eq!(
+            entry(&program.to_string_lossy()).exclusion(&environment),
+            None
+        );
+        let exclusion = Some(Exclusion::TryExec("data".to_owned()));
+        assert_eq!(entry("data").exclusion(&environment), exclusion);
+        assert!(entry("missing").exclusion(&environment).is_some());
+        fs::remove_dir_all(folder).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_desktop_id() {
+        let folder = Path::new("/usr/share/applications");
+        let id = desktop_id(folder, &folder.join("kde/konsole.desktop"));
+        assert_eq!(id, "kde-konsole.desktop");
+        assert_eq!(
+            desktop_id(folder, &folder.join("firefox.desktop")),
+            "firefox.desktop"
+        );
+    }
+
+    #[test]
+    fn test_actions() {
+        let entry = DesktopEntry::parse(FIREFOX);
+        let actions = entry.actions();
+        let names: Vec<_> = actions.iter().map(|(action, _)| *action).collect();
+        assert_eq!(names, vec!["new-window", "new-private-window"]);
+        let private = entry.action("new-private-window").expect("Action missed");
+        let locales = vec!["de".to_owned()];
+        assert_eq!(
+            private.localized("Name", &locales),
+            Some("Neues privates Fenster")
+        );
+        let path = Path::new("/usr/share/applications/firefox.desktop");
+        let expected = vec!["firefox", "--private-window"];
+        assert_eq!(
+            command_line(&entry, Some(private), path, &locales),
+            Some(expected.into_iter().map(String::from).collect())
+        );
+        assert!(entry.action("unlisted").is_none());
+        assert_eq!(entry.get("Exec"), Some(r#"firefox --name "Fire fox" %u"#));
+    }
+
+    #[test]
+    fn test_action_id() {
+        let path = Path::new("/usr/share/applications/firefox.desktop");
+        let id = action_id(path, "new-window");
+        assert_eq!(id, "/usr/share/applications/firefox.desktop#new-window");
+        assert_eq!(split_action_id(&id), Some((path, "new-window")));
+        assert_eq!(
+            split_action_id("/usr/share/applications/firefox.desktop"),
+            None
+        );
+        assert_eq!(split_action_id("/Applications/C#.app"), None);
+    }
+
+    #[test]
+    fn test_command_line() {
+        let path = Path::new("/usr/share/applications/firefox.desktop");
+        let locales = ["de".to_owned(), "en".to_owned()];
+        let entry = DesktopEntry::parse(FIREFOX);
+        let expected = vec!["firefox", "--name", "Fire fox"];
+        assert_eq!(
+            command_line(&entry, None, path, &locales),
+            Some(expected.into_iter().map(String::from).collect())
+        );
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nExec=\"/opt/my app/run\" %i --title=%c --from=%k 100%%\nIcon=app\nName=App\n\
+             Actions=big;\n[Desktop Action big]\nName=Big\nIcon=big\nExec=run --big %i --title=%c\n",
+        );
+        let expected = vec![
+            "/opt/my app/run",
+            "--icon",
+            "app",
+            "--title=App",
+            "--from=/usr/share/applications/firefox.desktop",
+            "100%",
+        ];
+        assert_eq!(
+            command_line(&entry, None, path, &locales),
+            Some(expected.into_iter().map(String::from).collect())
+        );
+        // Actions run their own Exec line, with the name and icon of the application
+        let expected = vec!["run", "--big", "--icon", "app", "--title=App"];
+        assert_eq!(
+            command_line(&entry, entry.action("big"), path, &locales),
+            Some(expected.into_iter().map(String::from).collect())
+        );
+        let unterminated = DesktopEntry::parse("[Desktop Entry]\nExec=\"run\n");
+        assert_eq!(command_line(&unterminated, None, path, &locales), None);
+    }
+}
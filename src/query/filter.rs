@@ -0,0 +1,297 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::configurator::schema::{self, Schema};
+use crate::query::checker::Outcome;
+use crate::query::kind::Kind;
+
+/// Step of the filter pipeline: when its condition holds for a path, its action decides
+/// what becomes of the path, and the later filters are skipped
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Filter {
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// What to do with matching paths, the default action of the condition if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+    /// Whether the action applies to the paths not matching the condition instead
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub invert: bool,
+    /// Kind reported for included paths, instead of the kind of their extension
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<Kind>,
+}
+
+/// What a filter tells the walker to do with a path
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    /// Skip the path, and everything under it
+    Prune,
+    /// Report the path as a service
+    Include,
+    /// Walk into the path
+    Descend,
+}
+
+/// Test of a filter, named by `rule` in the settings
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "rule", rename_all = "camelCase")]
+pub enum Condition {
+    /// Names starting with a dot
+    Hidden,
+    /// Symlinks not followed by the root, broken ones, and those resolved to an ignored path
+    Symlink,
+    /// Paths matching `ignorePaths`
+    Ignore,
+    /// Bundles with an extension of `bundleKinds` listed under the root
+    Bundle,
+    /// Names matching the regular expression
+    Name { pattern: Pattern },
+    /// Files of at least `bytes` bytes
+    MinSize { bytes: u64 },
+    /// Files modified within the last `days` days
+    ModifiedSince { days: u64 },
+    /// Files owned by the user
+    Owner { uid: u32 },
+    /// Files anyone may execute
+    Executable,
+}
+
+/// Names of the conditions, as given by `rule`
+const RULES: &[&str] = &[
+    "hidden",
+    "symlink",
+    "ignore",
+    "bundle",
+    "name",
+    "minSize",
+    "modifiedSince",
+    "owner",
+    "executable",
+];
+
+impl Schema for Filter {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "rule": {"type": "string", "enum": RULES},
+                "pattern": String::schema(),
+                "bytes": u64::schema(),
+                "days": u64::schema(),
+                "uid": u64::schema(),
+                "action": schema::of::<Action>(),
+                "invert": bool::schema(),
+                "kind": Kind::schema()
+            },
+            "required": ["rule"],
+            "additionalProperties": false
+        })
+    }
+}
+
+impl Schema for Action {
+    fn schema() -> Value {
+        json!({"type": "string"})
+    }
+
+    fn allowed_values() -> Option<Vec<Value>> {
+        Some(vec![json!("prune"), json!("include"), json!("descend")])
+    }
+}
+
+/// Regular expression, kept as written in the settings
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern).map(Pattern)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.as_str().to_owned()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// The built-in pipeline: hidden paths, symlinks and ignored paths are pruned,
+/// bundles are included, and every other folder is walked into
+pub const DEFAULT_FILTERS: &[Filter] = &[
+    Filter::built_in(Condition::Hidden),
+    Filter::built_in(Condition::Symlink),
+    Filter::built_in(Condition::Ignore),
+    Filter::built_in(Condition::Bundle),
+];
+
+impl Filter {
+    /// Filter with the default action of the condition
+    const fn built_in(condition: Condition) -> Self {
+        Filter {
+            condition,
+            action: None,
+            invert: false,
+            kind: None,
+        }
+    }
+
+    pub fn defaults() -> Vec<Filter> {
+        DEFAULT_FILTERS.to_vec()
+    }
+
+    /// Outcome of the filter for a path, given whether its condition holds,
+    /// and the kind of its extension if it has one.
+    /// A path the filter would include without any kind is left to the next filters.
+    pub fn outcome(&self, matched: bool, kind: Option<&Kind>) -> Option<Outcome> {
+        if matched == self.invert {
+            return None;
+        }
+        let action = self
+            .action
+            .unwrap_or_else(|| self.condition.default_action());
+        Some(match action {
+            Action::Prune => Outcome::UnwantedPath,
+            Action::Include => Outcome::BundlePath(self.kind.as_ref().or(kind)?.clone()),
+            Action::Descend => Outcome::NormalPath,
+        })
+    }
+}
+
+impl Condition {
+    fn default_action(&self) -> Action {
+        match self {
+            Condition::Bundle => Action::Include,
+            _ => Action::Prune,
+        }
+    }
+
+    /// Whether a condition on the name or the metadata holds, or `None` for built-in conditions,
+    /// which depend on the settings and the root
+    pub fn matches(&self, path: &Path) -> Option<bool> {
+        let metadata = || fs::metadata(path).ok();
+        let matched = match self {
+            Condition::Hidden | Condition::Symlink | Condition::Ignore | Condition::Bundle => {
+                return None
+            }
+            Condition::Name { pattern } => path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| pattern.0.is_match(name)),
+            Condition::MinSize { bytes } => {
+                metadata().is_some_and(|metadata| metadata.is_file() && metadata.len() >= *bytes)
+            }
+            Condition::ModifiedSince { days } => {
+                let since = SystemTime::now()
+                    .checked_sub(Duration::from_secs(days.saturating_mul(24 * 60 * 60)))
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                metadata()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .is_some_and(|modified| modified >= since)
+            }
+            Condition::Owner { uid } => metadata().is_some_and(|metadata| metadata.uid() == *uid),
+            Condition::Executable => metadata().is_some_and(|metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            }),
+        };
+        Some(matched)
+    }
+}
+
+#[cfg(test)]
+mod filter_test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::query::checker::Outcome;
+    use crate::query::filter::{Action, Condition, Filter};
+    use crate::query::kind::Kind;
+
+    fn filter(value: serde_json::Value) -> Filter {
+        serde_json::from_value(value).expect("Failed to parse")
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let name = filter(json!({"rule": "name", "pattern": "Uninstaller", "action": "prune"}));
+        assert_eq!(name.action, Some(Action::Prune));
+        assert_eq!(
+            name.condition
+                .matches(Path::new("/Applications/Adobe Uninstaller.app")),
+            Some(true)
+        );
+        assert!(serde_json::from_value::<Filter>(json!({"rule": "name", "pattern": "("})).is_err());
+        assert!(serde_json::from_value::<Filter>(json!({"rule": "unknown"})).is_err());
+        let serialized = serde_json::to_value(&name).expect("Failed to serialize");
+        assert_eq!(serialized["pattern"], json!("Uninstaller"));
+    }
+
+    #[test]
+    fn test_outcome() {
+        let bundle = Filter::built_in(Condition::Bundle);
+        assert!(matches!(
+            bundle.outcome(true, Some(&Kind::PreferencePane)),
+            Some(Outcome::BundlePath(Kind::PreferencePane))
+        ));
+        assert!(bundle.outcome(false, None).is_none());
+        let inverted = filter(json!({"rule": "executable", "invert": true}));
+        assert!(matches!(
+            inverted.outcome(false, None),
+            Some(Outcome::UnwantedPath)
+        ));
+        assert!(inverted.outcome(true, None).is_none());
+        let include =
+            filter(json!({"rule": "executable", "action": "include", "kind": "appImage"}));
+        assert!(matches!(
+            include.outcome(true, None),
+            Some(Outcome::BundlePath(Kind::AppImage))
+        ));
+        let include = filter(json!({"rule": "name", "pattern": "^Go", "action": "include"}));
+        assert!(include.outcome(true, None).is_none());
+        assert!(matches!(
+            include.outcome(true, Some(&Kind::Application)),
+            Some(Outcome::BundlePath(Kind::Application))
+        ));
+    }
+
+    #[test]
+    fn test_metadata_conditions() {
+        let path = std::env::temp_dir().join(format!("filter-{}", std::process::id()));
+        fs::write(&path, b"#!/bin/sh\n").expect("Failed to write");
+        let executable = Condition::Executable;
+        assert_eq!(executable.matches(&path), Some(false));
+        let mut permissions = fs::metadata(&path).expect("Failed").permissions();
+        permissions.set_mode(0o755);
+        fs::set_permissions(&path, permissions).expect("Failed to set mode");
+        assert_eq!(executable.matches(&path), Some(true));
+        assert_eq!(Condition::MinSize { bytes: 4 }.matches(&path), Some(true));
+        assert_eq!(
+            Condition::MinSize { bytes: 1024 }.matches(&path),
+            Some(false)
+        );
+        assert_eq!(
+            Condition::ModifiedSince { days: 1 }.matches(&path),
+            Some(true)
+        );
+        assert_eq!(Condition::Hidden.matches(&path), None);
+        fs::remove_file(path).expect("Failed to clean up");
+    }
+}
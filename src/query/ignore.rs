@@ -0,0 +1,261 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::utils;
+
+/// Part of an ignore pattern between two separators
+#[derive(Debug, PartialEq)]
+enum Segment {
+    /// `**`, matching any number of path components
+    AnyDepth,
+    /// A file name, where `*` matches any characters and `?` matches a single one
+    Name(String),
+}
+
+/// Single gitignore-style pattern of `ignorePaths`.
+/// Absolute patterns are anchored at the root, others match at any depth,
+/// and a pattern matching a folder matches everything under it too.
+#[derive(Debug)]
+pub struct Rule {
+    negated: bool,
+    segments: Vec<Segment>,
+}
+
+impl Rule {
+    /// Parse a pattern, where a leading `!` re-includes the paths it matches
+    pub fn parse(pattern: &str) -> Self {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let pattern = utils::expand_tilde(pattern);
+        let mut segments = Vec::new();
+        if !pattern.has_root() {
+            segments.push(Segment::AnyDepth);
+        }
+        for name in normalize(&pattern) {
+            match name.as_str() {
+                "**" if segments.last() == Some(&Segment::AnyDepth) => (),
+                "**" => segments.push(Segment::AnyDepth),
+                _ => segments.push(Segment::Name(name)),
+            }
+        }
+        Rule { negated, segments }
+    }
+
+    /// Longest folder the pattern is confined to, or `None` if it matches at any depth
+    pub fn base(&self) -> Option<PathBuf> {
+        let mut base = PathBuf::from("/");
+        for segment in &self.segments {
+            match segment {
+                Segment::Name(name) if !is_glob(name) => base.push(name),
+                Segment::AnyDepth if base.parent().is_none() => return None,
+                _ => break,
+            }
+        }
+        Some(base)
+    }
+
+    /// Whether the pattern matches the path or one of its ancestors
+    fn matches(&self, names: &[String]) -> bool {
+        matches_prefix(&self.segments, names)
+    }
+
+    /// Whether the pattern may match a path under the folder
+    fn matches_below(&self, names: &[String]) -> bool {
+        matches_below(&self.segments, names)
+    }
+}
+
+/// Ordered ignore patterns, where the last pattern matching a path decides
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let rules = patterns
+            .iter()
+            .map(|pattern| Rule::parse(pattern.as_ref()))
+            .collect();
+        IgnoreRules { rules }
+    }
+
+    /// Whether the path is ignored. A folder holding a re-included path is not ignored,
+    /// so that it is still walked into.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let names = normalize(path);
+        self.rules.iter().fold(false, |ignored, rule| {
+            if rule.matches(&names) {
+                !rule.negated
+            } else if ignored && rule.negated && rule.matches_below(&names) {
+                false
+            } else {
+                ignored
+            }
+        })
+    }
+}
+
+/// Names of the path components, with `.` and `..` resolved and the root dropped
+fn normalize(path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => names.push(name.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                names.pop();
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => (),
+        }
+    }
+    names
+}
+
+fn is_glob(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+fn matches_prefix(segments: &[Segment], names: &[String]) -> bool {
+    match segments.split_first() {
+        None => true,
+        Some((Segment::AnyDepth, rest)) => {
+            (0..=names.len()).any(|skip| matches_prefix(rest, &names[skip..]))
+        }
+        Some((Segment::Name(pattern), rest)) => match names.split_first() {
+            Some((name, names)) => matches_name(pattern, name) && matches_prefix(rest, names),
+            None => false,
+        },
+    }
+}
+
+fn matches_below(segments: &[Segment], names: &[String]) -> bool {
+    match (segments.split_first(), names.split_first()) {
+        (None, _) => true,
+        (Some(_), None) | (Some((Segment::AnyDepth, _)), Some(_)) => true,
+        (Some((Segment::Name(pattern), rest)), Some((name, names))) => {
+            matches_name(pattern, name) && matches_below(rest, names)
+        }
+    }
+}
+
+/// Match a file name against a pattern, where `*` matches any characters and `?` a single one
+fn matches_name(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&char) if char == '?' || char == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&char| char == '*')
+}
+
+#[cfg(test)]
+mod ignore_test {
+    use std::path::{Path, PathBuf};
+
+    use crate::query::ignore::{matches_name, IgnoreRules, Rule};
+
+    fn is_ignored(patterns: &[&str], path: &str) -> bool {
+        IgnoreRules::new(patterns).is_ignored(Path::new(path))
+    }
+
+    #[test]
+    fn test_matches_name() {
+        assert!(matches_name("Adobe*", "Adobe Photoshop"));
+        assert!(matches_name("*Uninstaller.app", "Adobe Uninstaller.app"));
+        assert!(matches_name("?ooks.app", "Books.app"));
+        assert!(matches_name("*a*b", "xaab"));
+        assert!(!matches_name("*Uninstaller.app", "Uninstaller.app.bak"));
+        assert!(!matches_name("Adobe", "Adobe Photoshop"));
+    }
+
+    #[test]
+    fn test_subtree() {
+        assert!(is_ignored(&["/Users/cheng"], "/Users/cheng/Applications"));
+        assert!(is_ignored(&["/Users/cheng/"], "/Users/cheng"));
+        assert!(!is_ignored(&["/Users/cheng"], "/Users/chengx"));
+        assert!(!is_ignored(&["/Users/cheng"], "/Users"));
+    }
+
+    #[test]
+    fn test_globs() {
+        let patterns = ["/Applications/Adobe*/**", "**/*Uninstaller.app"];
+        assert!(is_ignored(
+            &patterns,
+            "/Applications/Adobe Photoshop/Photoshop.app"
+        ));
+        assert!(is_ignored(
+            &patterns,
+            "/Applications/Tools/Adobe Uninstaller.app"
+        ));
+        assert!(!is_ignored(&patterns, "/Applications/Safari.app"));
+        assert!(is_ignored(
+            &["*.prefPane"],
+            "/System/Library/PreferencePanes/Network.prefPane"
+        ));
+    }
+
+    #[test]
+    fn test_negation() {
+        let patterns = [
+            "/Applications/Adobe*",
+            "!/Applications/Adobe Photoshop/Photoshop.app",
+        ];
+        assert!(is_ignored(&patterns, "/Applications/Adobe Bridge"));
+        assert!(!is_ignored(&patterns, "/Applications/Adobe Photoshop"));
+        assert!(is_ignored(
+            &patterns,
+            "/Applications/Adobe Photoshop/Uninstall.app"
+        ));
+        assert!(!is_ignored(
+            &patterns,
+            "/Applications/Adobe Photoshop/Photoshop.app"
+        ));
+        let patterns = ["!/Applications/Safari.app", "/Applications"];
+        assert!(is_ignored(&patterns, "/Applications/Safari.app"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert!(is_ignored(
+            &["/Applications/../Library/./Foo"],
+            "/Library/Foo"
+        ));
+        assert!(is_ignored(&["/Library/Foo"], "/Library/Bar/../Foo/Baz.app"));
+        assert!(is_ignored(
+            &["~/Applications"],
+            &format!("{}/Applications", std::env::var("HOME").unwrap_or_default())
+        ));
+    }
+
+    #[test]
+    fn test_base() {
+        let base = |pattern: &str| Rule::parse(pattern).base();
+        assert_eq!(
+            base("/Applications/Adobe*/**"),
+            Some(PathBuf::from("/Applications"))
+        );
+        assert_eq!(base("!/Users/cheng/"), Some(PathBuf::from("/Users/cheng")));
+        assert_eq!(base("/**/*.app"), None);
+        assert_eq!(base("*Uninstaller.app"), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::configurator::schema::Schema;
+
+/// Kind of item a bundle extension stands for
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum Kind {
+    Application,
+    PreferencePane,
+    AppExtension,
+    Workflow,
+    QuickLookGenerator,
+    ScreenSaver,
+    DesktopEntry,
+    AppImage,
+    /// Kind named in the settings, for an extension with no built-in kind
+    Custom(String),
+}
+
+impl Kind {
+    /// Kinds of the bundle extensions listed by default, keyed by extension
+    pub fn defaults() -> HashMap<String, Kind> {
+        vec![
+            ("app", Kind::Application),
+            ("prefPane", Kind::PreferencePane),
+        ]
+        .into_iter()
+        .map(|(extension, kind)| (extension.to_owned(), kind))
+        .collect()
+    }
+
+    /// Name of the kind, as written in the settings and reported for services
+    pub fn name(&self) -> &str {
+        match self {
+            Kind::Application => "application",
+            Kind::PreferencePane => "preferencePane",
+            Kind::AppExtension => "appExtension",
+            Kind::Workflow => "workflow",
+            Kind::QuickLookGenerator => "quickLookGenerator",
+            Kind::ScreenSaver => "screenSaver",
+            Kind::DesktopEntry => "desktopEntry",
+            Kind::AppImage => "appImage",
+            Kind::Custom(name) => name,
+        }
+    }
+}
+
+impl Schema for Kind {
+    fn schema() -> Value {
+        json!({"type": "string"})
+    }
+}
+
+impl From<String> for Kind {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "application" => Kind::Application,
+            "preferencePane" => Kind::PreferencePane,
+            "appExtension" => Kind::AppExtension,
+            "workflow" => Kind::Workflow,
+            "quickLookGenerator" => Kind::QuickLookGenerator,
+            "screenSaver" => Kind::ScreenSaver,
+            "desktopEntry" => Kind::DesktopEntry,
+            "appImage" => Kind::AppImage,
+            _ => Kind::Custom(name),
+        }
+    }
+}
+
+impl From<Kind> for String {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Custom(name) => name,
+            kind => kind.name().to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod kind_test {
+    use crate::query::kind::Kind;
+
+    #[test]
+    fn test_serialize() {
+        let serialized = serde_json::to_string(&Kind::PreferencePane).expect("Failed");
+        assert_eq!(serialized, r#""preferencePane""#);
+        let kind: Kind = serde_json::from_str(r#""appImage""#).expect("Failed");
+        assert_eq!(kind, Kind::AppImage);
+    }
+
+    #[test]
+    fn test_custom() {
+        let kind: Kind = serde_json::from_str(r#""sketchDocument""#).expect("Failed");
+        assert_eq!(kind, Kind::Custom("sketchDocument".to_owned()));
+        let serialized = serde_json::to_string(&kind).expect("Failed");
+        assert_eq!(serialized, r#""sketchDocument""#);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let defaults = Kind::defaults();
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(defaults.get("app"), Some(&Kind::Application));
+        assert_eq!(defaults.get("prefPane"), Some(&Kind::PreferencePane));
+        assert_eq!(defaults.get("AppImage"), None);
+    }
+}
@@ -76,12 +76,12 @@ mod matcher_test {
     #[test]
     fn test_initial_match_overcomplete_query() {
         let res = initial_match("ams", "Activity Monitor.app");
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn test_initial_match_unmatched() {
         let res = initial_match("acx", "Activity Monitor.app");
-        assert_eq!(res, false);
+        assert!(!res);
     }
 }
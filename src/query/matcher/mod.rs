@@ -2,4 +2,5 @@ pub use cleaner::tokenize;
 pub use matcher::match_query;
 
 mod cleaner;
+#[allow(clippy::module_inception)]
 mod matcher;
@@ -1,12 +1,38 @@
-use std::io::stdout;
+use std::io::{self, stdin, stdout};
 
+pub use catalog::Catalog;
+pub use desktop::Exclusion;
+pub use filter::{Action, Condition, Filter};
+pub use ignore::IgnoreRules;
+pub use kind::Kind;
+pub use matcher::{match_query, tokenize};
 pub use query::QueryProcessor;
+pub use service::Service;
 
+pub(crate) use ignore::Rule;
+
+use crate::configurator::Configs;
+
+mod batch;
+mod bundle;
+mod catalog;
 mod checker;
+pub(crate) mod desktop;
+mod filter;
+mod ignore;
+mod kind;
 mod matcher;
+#[allow(clippy::module_inception)]
 mod query;
 mod service;
+mod walker;
+
+/// Print every service matching the query on stdout
+pub fn query(configs: &Configs, req: &str) -> io::Result<()> {
+    QueryProcessor::new(configs, stdout().lock()).query(req)
+}
 
-pub fn query(req: &str) {
-    QueryProcessor::new(stdout()).query(req);
+/// Answer one query per line from stdin, walking the configured paths only once
+pub fn query_batch(configs: &Configs) -> io::Result<()> {
+    batch::query_batch(&Catalog::new(configs), stdin().lock(), stdout().lock())
 }
@@ -1,76 +1,96 @@
-use std::fs::read_dir;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
 
-use crate::query::checker::{Checker, Outcome};
-use crate::query::matcher;
+use crate::configurator::{Configs, Root};
+use crate::query::desktop::DesktopEntry;
+use crate::query::kind::Kind;
 use crate::query::service::Service;
+use crate::query::walker::{self, Visited};
 use crate::utils::serde::serialize_to_string;
 
 pub struct QueryProcessor<'a, W: Write> {
-    checker: Checker<'a>,
+    configs: &'a Configs,
     output: W,
+    /// Folders and bundles walked by the current query
+    visited: Visited,
+    /// First failure to write the output, after which nothing more is written
+    failure: Option<io::Error>,
 }
 
 impl<'a, W: Write> QueryProcessor<'a, W> {
     /// New query processor
-    pub fn new(writer: W) -> Self {
-        let ignored_paths = crate::CONFIG.get_ignore_paths();
+    pub fn new(configs: &'a Configs, writer: W) -> Self {
         QueryProcessor {
-            checker: Checker::new(ignored_paths),
+            configs,
             output: writer,
+            visited: Visited::default(),
+            failure: None,
         }
     }
 
-    /// Query based on the request, and return serialized bytes of the services
-    pub fn query(&mut self, req: &str) {
+    /// Query based on the request, and write the serialized services, one per line.
+    /// Fails if the output cannot be written, e.g. once its reader is gone.
+    pub fn query(&mut self, req: &str) -> io::Result<()> {
         let req = req.to_lowercase();
-        crate::CONFIG
-            .get_paths()
-            .into_iter()
-            .for_each(|path| self.walk_paths(path, &req));
+        let configs = self.configs;
+        self.visited = Visited::default();
+        configs
+            .get_roots()
+            .iter()
+            .for_each(|root| self.walk_paths(root, &req));
+        self.failure.take().map_or(Ok(()), Err)
     }
 
-    /// Recursively iterate through files and folders, and return all legit file paths
-    fn walk_paths<P: AsRef<Path>>(&mut self, entry: P, query: &str) {
-        let entry = entry.as_ref();
-        match self.checker.check(entry) {
-            Outcome::UnwantedPath => (),
-            Outcome::BundlePath => self.filter_output_path(entry, query),
-            Outcome::NormalPath => {
-                let mut read_folder = match read_dir(&entry) {
-                    Ok(read_folder) => read_folder,
-                    Err(err) => {
-                        eprintln!("Read folder error: {}", err);
-                        return;
-                    }
-                };
-                while let Some(Ok(path)) = read_folder.next() {
-                    self.walk_paths(path.path(), query);
+    /// Recursively iterate through files and folders, and output the matched bundles
+    fn walk_paths(&mut self, root: &Root, query: &str) {
+        let (configs, output, failure) = (self.configs, &mut self.output, &mut self.failure);
+        walker::walk_root(
+            configs,
+            root,
+            &mut self.visited,
+            &mut |path, kind, entry| {
+                if failure.is_some() {
+                    return;
                 }
-            }
-        }
+                let found = (path, kind, entry);
+                if let Err(error) = Self::filter_output_path(configs, output, root, found, query) {
+                    *failure = Some(error);
+                }
+            },
+        );
     }
 
-    fn filter_output_path(&mut self, path: &Path, query: &str) {
-        let service = Service::new(path);
-        if matcher::match_query(query, &service.title) {
+    fn filter_output_path(
+        configs: &Configs,
+        output: &mut W,
+        root: &Root,
+        (path, kind, entry): (&Path, Kind, Option<&DesktopEntry>),
+        query: &str,
+    ) -> io::Result<()> {
+        let service =
+            Service::with_entry(path, kind, entry, configs).with_label(root.label.clone());
+        let actions = entry
+            .map(|entry| service.actions(entry, configs))
+            .unwrap_or_default();
+        for service in std::iter::once(service).chain(actions) {
+            if !service.matches(query) {
+                continue;
+            }
             match serialize_to_string(&service) {
-                Ok(service) => {
-                    if let Err(error) = writeln!(self.output, "{}", service) {
-                        eprintln!("Failed to write: {}", error);
-                    }
-                }
+                Ok(service) => writeln!(output, "{}", service)?,
                 Err(error) => eprintln!("Failed to serialize: {}", error),
             }
         }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod query_test {
+    use std::io::{self, Write};
     use std::path::PathBuf;
 
+    use crate::configurator::{get_configs, Root};
     use crate::query::query::QueryProcessor;
 
     type QP<'a, W> = QueryProcessor<'a, W>;
@@ -80,28 +100,48 @@ mod query_test {
 
     #[test]
     fn test_walk_dir_single() {
-        let settings = crate::configurator::get_content();
-        std::env::set_var("SETTINGS", settings);
+        let configs = get_configs();
         let mut output = Vec::<u8>::new();
-        let mut processor = QP::new(&mut output);
-        let single_file = PathBuf::from(APP_PATH);
+        let mut processor = QP::new(&configs, &mut output);
+        let single_file = Root::from(PathBuf::from(APP_PATH));
         processor.walk_paths(&single_file, "book");
         let string = String::from_utf8(output).expect("Failed to parse");
-        let expected = r#"{"title":"Books","subtitle":"/System/Applications/Books.app","id":"/System/Applications/Books.app"}
+        let expected = r#"{"title":"Books","subtitle":"/System/Applications/Books.app","id":"/System/Applications/Books.app","kind":"application"}
 "#;
         assert_eq!(string, expected);
     }
 
     #[test]
     fn test_walk_dir_all_apps_starts_with_a() {
-        let settings = crate::configurator::get_content();
-        std::env::set_var("SETTINGS", settings);
+        let configs = get_configs();
         let mut output = Vec::new();
-        let mut processor = QP::new(&mut output);
-        let content = PathBuf::from(APP_FOLDER_PATH);
+        let mut processor = QP::new(&configs, &mut output);
+        let content = Root::from(PathBuf::from(APP_FOLDER_PATH));
         processor.walk_paths(&content, "a");
         let string = String::from_utf8(output).expect("Failed to parse");
         let lines = string.split("\n").collect::<Vec<_>>();
         assert_eq!(lines.len(), 9);
     }
+
+    /// Output whose reader is gone
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_walk_dir_closed_output() {
+        let configs = get_configs();
+        let mut processor = QP::new(&configs, ClosedPipe);
+        processor.walk_paths(&Root::from(PathBuf::from(APP_PATH)), "book");
+        let failure = processor.failure.take().expect("Failure missed");
+        assert_eq!(failure.kind(), io::ErrorKind::BrokenPipe);
+    }
 }
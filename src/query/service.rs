@@ -4,9 +4,13 @@ use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 use super::matcher;
+use crate::configurator::{Alias, Configs};
+use crate::query::bundle;
+use crate::query::desktop::{self, DesktopEntry};
+use crate::query::kind::Kind;
 
-fn map_term(name: &str) -> String {
-    crate::CONFIG
+fn map_term(name: &str, configs: &Configs) -> String {
+    configs
         .get_pref_names()
         .get(name)
         .map(String::to_owned)
@@ -18,25 +22,143 @@ pub struct Service {
     pub title: String,
     subtitle: PathBuf,
     id: PathBuf,
+    kind: Kind,
+    /// Label of the root the service is found under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// Search keywords matched along with the title
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
 }
 
 impl Service {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+    pub fn new<P: AsRef<Path>>(path: P, kind: Kind, configs: &Configs) -> Self {
         let path = path.as_ref();
-        let title = Self::file_name(&path);
+        let entry = match kind {
+            Kind::DesktopEntry => DesktopEntry::read(path).ok(),
+            _ => None,
+        };
+        Self::with_entry(path, kind, entry.as_ref(), configs)
+    }
+
+    /// Service of the bundle, with its desktop entry already read if it is one
+    pub(crate) fn with_entry(
+        path: &Path,
+        kind: Kind,
+        entry: Option<&DesktopEntry>,
+        configs: &Configs,
+    ) -> Self {
+        let mut title = Self::file_name(path, &kind, entry, configs);
+        let mut keywords = Vec::new();
+        if let Some(alias) = Self::alias(path, configs) {
+            keywords = alias.keywords.clone();
+            if let Some(alias_title) = alias.title.as_ref().filter(|alias| **alias != title) {
+                // The service is still found by its own name
+                keywords.push(std::mem::replace(&mut title, alias_title.to_owned()));
+            }
+        }
+        if let Some(english) = Self::english_name(path, &kind, configs) {
+            if *english != title {
+                keywords.push(english.to_owned());
+            }
+        }
         let subtitle = path.to_path_buf();
         Service {
             title,
             subtitle,
             id: path.to_path_buf(),
+            kind,
+            label: None,
+            keywords,
         }
     }
 
-    fn file_name<P: AsRef<Path>>(path: P) -> String {
-        let path = path.as_ref();
+    /// Whether the title or any keyword matches the lowercased query
+    pub fn matches(&self, query: &str) -> bool {
+        matcher::match_query(query, &self.title)
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| matcher::match_query(query, keyword))
+    }
+
+    /// Service labelled after the root it is found under
+    pub fn with_label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Label of the root the service is found under
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Path to the service, used as its identifier
+    pub fn id(&self) -> &Path {
+        &self.id
+    }
+
+    /// Actions of the desktop entry of the service, as services titled after the entry
+    /// and the action, e.g. `Firefox: New Private Window`, and identified by `desktop::action_id`
+    pub(crate) fn actions(&self, entry: &DesktopEntry, configs: &Configs) -> Vec<Service> {
+        entry
+            .actions()
+            .into_iter()
+            .filter_map(|(action, group)| {
+                let name = group.localized("Name", configs.get_locales())?;
+                Some(Service {
+                    title: format!("{}: {}", self.title, name),
+                    subtitle: self.subtitle.clone(),
+                    id: PathBuf::from(desktop::action_id(&self.id, action)),
+                    kind: self.kind.clone(),
+                    label: self.label.clone(),
+                    keywords: self.keywords.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Alias keyed by the path, or else by the bundle identifier
+    fn alias<'a>(path: &Path, configs: &'a Configs) -> Option<&'a Alias> {
+        let aliases = configs.get_aliases();
+        aliases.by_path(path).or_else(|| {
+            if aliases.has_identifiers() {
+                bundle::identifier(path).and_then(|identifier| aliases.by_identifier(&identifier))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Kind of item the service is
+    pub fn kind(&self) -> &Kind {
+        &self.kind
+    }
+
+    /// English name of a preference pane, matched along with its localized title
+    fn english_name<'a>(path: &Path, kind: &Kind, configs: &'a Configs) -> Option<&'a String> {
+        match kind {
+            Kind::PreferencePane => path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .and_then(|name| configs.get_english_pref_names().get(name)),
+            _ => None,
+        }
+    }
+
+    fn file_name(
+        path: &Path,
+        kind: &Kind,
+        entry: Option<&DesktopEntry>,
+        configs: &Configs,
+    ) -> String {
         let path_process = path.file_stem().and_then(OsStr::to_str);
-        match path.extension().and_then(|ext| ext.to_str()) {
-            Some("prefPane") => path_process.map(map_term),
+        match kind {
+            Kind::PreferencePane => path_process.map(|name| map_term(name, configs)),
+            Kind::DesktopEntry => entry
+                .and_then(|entry| entry.localized("Name", configs.get_locales()))
+                .map(String::from)
+                .or_else(|| path_process.map(String::from)),
             _ => path_process.map(String::from),
         }
         .unwrap_or_default()
@@ -45,20 +167,121 @@ impl Service {
 
 #[cfg(test)]
 mod service_test {
+    use crate::configurator::{get_configs, Configs};
+    use crate::query::desktop::DesktopEntry;
+    use crate::query::kind::Kind;
     use crate::query::service::Service;
 
     #[test]
     fn test_serialize() {
         let path = "/System/Applications/Book.app";
-        let service = Service::new(path);
+        let service = Service::new(path, Kind::Application, &get_configs());
+        let serialized = serde_json::to_string(&service).expect("Unable to serialize");
+        let expected = r#"{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app","kind":"application"}"#;
+        assert_eq!(serialized, expected);
+    }
+
+    #[test]
+    fn test_serialize_label() {
+        let path = "/opt/apps/Tool.app";
+        let service = Service::new(path, Kind::Application, &get_configs())
+            .with_label(Some("Team".to_owned()));
         let serialized = serde_json::to_string(&service).expect("Unable to serialize");
-        let expected = r#"{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app"}"#;
+        let expected = r#"{"title":"Tool","subtitle":"/opt/apps/Tool.app","id":"/opt/apps/Tool.app","kind":"application","label":"Team"}"#;
         assert_eq!(serialized, expected);
     }
 
+    #[test]
+    fn test_desktop_entry_title() {
+        let path = std::env::temp_dir().join(format!("service-{}.desktop", std::process::id()));
+        let content = "[Desktop Entry]\nType=Application\nName=Files\nName[de]=Dateien\n";
+        std::fs::write(&path, content).expect("Failed to write");
+        let configs = Configs::from(
+            r#"{"configurable": {"locale": {"value": "de_AT"}}, "internal": {"paths": [], "prefNames": {}}}"#,
+        )
+        .expect("Failed to parse");
+        let service = Service::new(&path, Kind::DesktopEntry, &configs);
+        assert_eq!(service.title, "Dateien");
+        std::fs::remove_file(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_desktop_entry_actions() {
+        let path =
+            std::env::temp_dir().join(format!("service-{}-actions.desktop", std::process::id()));
+        let content = "[Desktop Entry]\nType=Application\nName=Firefox\nActions=private;\n\n[Desktop Action private]\nName=New Private Window\nExec=firefox --private-window\n";
+        std::fs::write(&path, content).expect("Failed to write");
+        let entry = DesktopEntry::parse(content);
+        let service = Service::with_entry(&path, Kind::DesktopEntry, Some(&entry), &get_configs())
+            .with_label(Some("Apps".to_owned()));
+        let actions = service.actions(&entry, &get_configs());
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Firefox: New Private Window");
+        let id = format!("{}#private", path.display());
+        assert_eq!(actions[0].id(), std::path::Path::new(&id));
+        assert_eq!(actions[0].label(), Some("Apps"));
+        assert!(actions[0].matches("firefox"));
+        assert!(actions[0].matches("priv"));
+        std::fs::remove_file(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_pref_pane_title() {
+        let path = "/System/Library/PreferencePanes/Localization.prefPane";
+        let service = Service::new(path, Kind::PreferencePane, &get_configs());
+        assert_eq!(service.title, "Language & Region");
+    }
+
+    #[test]
+    fn test_pref_pane_english_title() {
+        let configs = Configs::from(
+            r#"{
+                "configurable": {"locale": {"value": "de"}},
+                "internal": {"paths": [], "prefNames": {
+                    "en": {"Localization": "Language & Region"},
+                    "de": {"Localization": "Sprache & Region"}
+                }}
+            }"#,
+        )
+        .expect("Failed to parse");
+        let path = "/System/Library/PreferencePanes/Localization.prefPane";
+        let service = Service::new(path, Kind::PreferencePane, &configs);
+        assert_eq!(service.title, "Sprache & Region");
+        assert!(service.matches("sprache"));
+        assert!(service.matches("language"));
+    }
+
+    #[test]
+    fn test_alias() {
+        let configs = Configs::from(
+            r#"{
+                "configurable": {"aliases": {"value": {
+                    "/Applications/Visual Studio Code.app": {"keywords": ["vsc"], "title": "Code"}
+                }}},
+                "internal": {"paths": [], "prefNames": {}}
+            }"#,
+        )
+        .expect("Failed to parse");
+        let service = Service::new(
+            "/Applications/Visual Studio Code.app",
+            Kind::Application,
+            &configs,
+        );
+        assert_eq!(service.title, "Code");
+        assert!(service.matches("vsc"));
+        assert!(service.matches("co"));
+        assert!(service.matches("visual"));
+        assert_eq!(
+            service.keywords.last().map(String::as_str),
+            Some("Visual Studio Code")
+        );
+        let service = Service::new("/Applications/Safari.app", Kind::Application, &configs);
+        assert!(!service.matches("vsc"));
+    }
+
     #[test]
     fn test_deserialize() {
-        let source = r#"{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app"}"#;
+        let source = r#"{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app","kind":"application"}"#;
         let service: Service = serde_json::from_str(source).expect("Unable to deserialize");
         let expected = "/System/Applications/Book.app";
         assert_eq!(service.id.to_str().unwrap_or(""), expected);
@@ -66,19 +289,24 @@ mod service_test {
 
     #[test]
     fn test_bunch_serialize() {
+        let configs = get_configs();
         let services = vec![
-            Service::new("/System/Applications/Book.app"),
-            Service::new("/System/Applications/Safari.app"),
+            Service::new("/System/Applications/Book.app", Kind::Application, &configs),
+            Service::new(
+                "/System/Applications/Safari.app",
+                Kind::Application,
+                &configs,
+            ),
         ];
         let serialized = serde_json::to_string(&services).expect("Unable to serialize");
-        let expected = r#"[{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app"},{"title":"Safari","subtitle":"/System/Applications/Safari.app","id":"/System/Applications/Safari.app"}]"#;
+        let expected = r#"[{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app","kind":"application"},{"title":"Safari","subtitle":"/System/Applications/Safari.app","id":"/System/Applications/Safari.app","kind":"application"}]"#;
         assert_eq!(serialized, expected);
     }
 
     #[test]
     fn test_bunch_deserialize() {
-        let source = r#"[{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app"}
-        ,{"title":"Safari","subtitle":"/System/Applications/Safari.app","id":"/System/Applications/Safari.app"}]"#;
+        let source = r#"[{"title":"Book","subtitle":"/System/Applications/Book.app","id":"/System/Applications/Book.app","kind":"application"}
+        ,{"title":"Safari","subtitle":"/System/Applications/Safari.app","id":"/System/Applications/Safari.app","kind":"application"}]"#;
         let services: Vec<Service> = serde_json::from_str(source).expect("Unable to deserialize");
         let services = services
             .iter()
@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use std::fs::{self, read_dir};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::configurator::{Configs, Root};
+use crate::query::checker::{Checker, Outcome};
+use crate::query::desktop::{self, DesktopEntry, Environment, Exclusion};
+use crate::query::kind::Kind;
+
+/// Device and inode of a file, the same whichever link it is reached through
+type FileId = (u64, u64);
+
+/// Bundles already found, shared by every root of a walk, so a bundle reached through
+/// several links or roots is found once. Folders are walked again under every root,
+/// which may list other extensions or go deeper, and only symlink cycles are cut short.
+/// Desktop entries are found once per desktop-file ID, the one of the first root shadowing the others.
+#[derive(Default)]
+pub struct Visited {
+    /// Folders being walked, from the root down, which a symlink cycle leads back to
+    ancestors: Vec<FileId>,
+    bundles: HashSet<FileId>,
+    desktop_ids: HashSet<String>,
+    /// Environment desktop entries are shown in, read when the first one is found
+    environment: Option<Environment>,
+    /// Desktop entries not found, with the reason
+    excluded: Vec<(PathBuf, Exclusion)>,
+}
+
+impl Visited {
+    /// Remember the bundle, and return whether it is seen for the first time.
+    /// Bundles that cannot be read are always new.
+    fn insert_bundle(&mut self, path: &Path) -> bool {
+        match file_id(path) {
+            Some(id) => self.bundles.insert(id),
+            None => true,
+        }
+    }
+
+    /// Desktop entries not found, with the reason
+    pub fn into_excluded(self) -> Vec<(PathBuf, Exclusion)> {
+        self.excluded
+    }
+}
+
+/// Identifier of the file the path leads to, `None` if it cannot be read
+fn file_id(path: &Path) -> Option<FileId> {
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+/// Walk through the root with its own options, and pass every bundle path to `found`
+pub fn walk_root<F: FnMut(&Path, Kind, Option<&DesktopEntry>)>(
+    configs: &Configs,
+    root: &Root,
+    visited: &mut Visited,
+    found: &mut F,
+) {
+    let checker = Checker::new(configs.get_ignore_rules(), configs.get_bundle_kinds())
+        .with_root(root)
+        .with_filters(configs.get_filters());
+    walk(&checker, &root.path, root.depth, visited, found);
+}
+
+/// Recursively iterate through files and folders, at most `depth` folders deep,
+/// and pass every bundle path to `found`, along with its kind, and its entry if it is a desktop entry.
+/// Bundles are reported by the path they are reached through, even if it is a symlink.
+pub fn walk<P: AsRef<Path>, F: FnMut(&Path, Kind, Option<&DesktopEntry>)>(
+    checker: &Checker,
+    entry: P,
+    depth: Option<usize>,
+    visited: &mut Visited,
+    found: &mut F,
+) {
+    let entry = entry.as_ref();
+    match checker.check(entry) {
+        Outcome::UnwantedPath => (),
+        Outcome::BundlePath(Kind::DesktopEntry) => {
+            let id = desktop::desktop_id(checker.root().unwrap_or(entry), entry);
+            // Entries not shown still shadow the ones with the same ID
+            if !visited.desktop_ids.insert(id.clone()) {
+                let exclusion = Exclusion::Shadowed(id);
+                visited.excluded.push((entry.to_path_buf(), exclusion));
+                return;
+            }
+            if !visited.insert_bundle(entry) {
+                return;
+            }
+            let environment = visited.environment.get_or_insert_with(Environment::current);
+            let desktop_entry = match DesktopEntry::read(entry) {
+                Ok(desktop_entry) => desktop_entry,
+                Err(error) => {
+                    let exclusion = Exclusion::Unreadable(error.to_string());
+                    visited.excluded.push((entry.to_path_buf(), exclusion));
+                    return;
+                }
+            };
+            match desktop_entry.exclusion(environment) {
+                Some(exclusion) => visited.excluded.push((entry.to_path_buf(), exclusion)),
+                None => found(entry, Kind::DesktopEntry, Some(&desktop_entry)),
+            }
+        }
+        Outcome::BundlePath(kind) => {
+            if visited.insert_bundle(entry) {
+                found(entry, kind, None)
+            }
+        }
+        Outcome::NormalPath if depth == Some(0) => (),
+        Outcome::NormalPath => {
+            let id = file_id(entry);
+            // A symlink back to a folder being walked would recurse endlessly
+            if id.is_some_and(|id| visited.ancestors.contains(&id)) {
+                return;
+            }
+            let mut read_folder = match read_dir(entry) {
+                Ok(read_folder) => read_folder,
+                Err(err) => {
+                    eprintln!("Read folder error: {}", err);
+                    return;
+                }
+            };
+            visited.ancestors.extend(id);
+            while let Some(Ok(path)) = read_folder.next() {
+                walk(
+                    checker,
+                    path.path(),
+                    depth.map(|depth| depth - 1),
+                    visited,
+                    found,
+                );
+            }
+            if id.is_some() {
+                visited.ancestors.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod walker_test {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    use crate::configurator::{Configs, Root};
+    use crate::query::walker::{walk_root, Visited};
+
+    fn root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("walker-{}-{}", name, std::process::id()));
+        fs::create_dir_all(root.join("Store/Editor.app")).expect("Failed to create");
+        fs::create_dir_all(root.join("Apps")).expect("Failed to create");
+        symlink(root.join("Store/Editor.app"), root.join("Apps/Editor.app"))
+            .expect("Failed to link");
+        symlink(&root, root.join("Apps/Loop")).expect("Failed to link");
+        root
+    }
+
+    fn found(configs: &Configs, roots: &[Root]) -> Vec<PathBuf> {
+        let mut visited = Visited::default();
+        let mut paths = Vec::new();
+        for root in roots {
+            walk_root(configs, root, &mut visited, &mut |path, _, _| {
+                paths.push(path.to_path_buf())
+            });
+        }
+        paths
+    }
+
+    fn configs(ignored: &[PathBuf]) -> Configs {
+        let settings = format!(
+            r#"{{"configurable":{{"ignorePaths":{{"value":{:?}}}}},"internal":{{"paths":[],"prefNames":{{}},"bundleKinds":{{"app":"application","desktop":"desktopEntry"}}}}}}"#,
+            ignored
+        );
+        Configs::from(settings).expect("Failed to parse")
+    }
+
+    fn following(path: PathBuf) -> Root {
+        let mut root = Root::from(path);
+        root.follow_symlinks = true;
+        root
+    }
+
+    #[test]
+    fn test_follow_symlinks() {
+        let root = root("follow");
+        let configs = configs(&[]);
+        let paths = found(&configs, &[following(root.join("Apps"))]);
+        assert_eq!(paths, vec![root.join("Apps/Editor.app")]);
+        let paths = found(&configs, &[Root::from(root.join("Apps"))]);
+        assert!(paths.is_empty());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_follow_symlinks_cycles() {
+        let root = root("cycles");
+        for name in ["Back", "Up", "Top"] {
+            symlink("..", root.join("Apps").join(name)).expect("Failed to link");
+        }
+        symlink(root.join("Apps"), root.join("Store/Apps")).expect("Failed to link");
+        let configs = configs(&[]);
+        let paths = found(&configs, &[following(root.clone())]);
+        // Found once, through whichever link is walked first
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("Editor.app"));
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_nested_roots() {
+        let root = root("nested");
+        let (outer, inner) = (root.join("A"), root.join("A/B"));
+        fs::create_dir_all(inner.join("Sub/Xylo.app")).expect("Failed to create");
+        let configs = configs(&[]);
+        let mut shallow = Root::from(outer);
+        shallow.depth = Some(2);
+        let paths = found(&configs, &[shallow, Root::from(inner.clone())]);
+        assert_eq!(paths, vec![inner.join("Sub/Xylo.app")]);
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_desktop_entries() {
+        let root = std::env::temp_dir().join(format!("walker-desktop-{}", std::process::id()));
+        let (user, system) = (root.join("user"), root.join("system"));
+        fs::create_dir_all(user.join("kde")).expect("Failed to create");
+        fs::create_dir_all(&system).expect("Failed to create");
+        let entry = |name: &str, extra: &str| {
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\n{}",
+                name, extra
+            )
+        };
+        let files = [
+            (user.join("kde/konsole.desktop"), entry("Konsole", "")),
+            (
+                user.join("editor.desktop"),
+                entry("Editor", "Hidden=true\n"),
+            ),
+            (system.join("editor.desktop"), entry("Editor", "")),
+            (system.join("kde-konsole.desktop"), entry("Konsole", "")),
+            (
+                system.join("helper.desktop"),
+                entry("Helper", "NoDisplay=true\n"),
+            ),
+            (system.join("files.desktop"), entry("Files", "")),
+        ];
+        for (path, content) in &files {
+            fs::write(path, content).expect("Failed to write");
+        }
+        let configs = configs(&[]);
+        let roots = [Root::from(user.clone()), Root::from(system.clone())];
+        let mut paths = found(&configs, &roots);
+        paths.sort();
+        let expected = vec![
+            system.join("files.desktop"),
+            user.join("kde/konsole.desktop"),
+        ];
+        assert_eq!(paths, expected);
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_follow_symlinks_dedup() {
+        let root = root("dedup");
+        let configs = configs(&[]);
+        let roots = [following(root.join("Apps")), Root::from(root.join("Store"))];
+        assert_eq!(found(&configs, &roots), vec![root.join("Apps/Editor.app")]);
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_follow_symlinks_ignored_target() {
+        let root = root("ignored");
+        let configs = configs(&[root.join("Store")]);
+        let paths = found(&configs, &[following(root.join("Apps"))]);
+        assert!(paths.is_empty());
+        fs::remove_dir_all(root).expect("Failed to clean up");
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Replace the file in a single step: the content is written to a sibling file first,
+/// which is then renamed over the file, so readers never see a partial write.
+/// A symlink is kept, and the file it leads to is replaced, keeping its permissions.
+pub fn write_atomically<P: AsRef<Path>>(path: P, content: &str) -> io::Result<()> {
+    let path = match fs::symlink_metadata(path.as_ref()) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.as_ref().to_path_buf(),
+    };
+    let path = path.as_path();
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temporary = path.with_file_name(name);
+    fs::write(&temporary, content)
+        .and_then(|_| match fs::metadata(path) {
+            Ok(metadata) => fs::set_permissions(&temporary, metadata.permissions()),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temporary, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+}
+
+#[cfg(test)]
+mod atomic_writer_test {
+    use std::fs;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    use crate::utils::atomic_writer::write_atomically;
+
+    #[test]
+    fn test_write_atomically() {
+        let folder = std::env::temp_dir().join(format!("atomic-{}", std::process::id()));
+        let path = folder.join("nested").join("settings.yaml");
+        write_atomically(&path, "a: 1\n").expect("Failed to write");
+        write_atomically(&path, "a: 2\n").expect("Failed to replace");
+        assert_eq!(fs::read_to_string(&path).expect("Failed to read"), "a: 2\n");
+        let files = fs::read_dir(path.parent().expect("No parent")).expect("Failed to list");
+        assert_eq!(files.count(), 1);
+        fs::remove_dir_all(folder).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn test_write_through_symlink() {
+        let folder = std::env::temp_dir().join(format!("atomic-link-{}", std::process::id()));
+        let (target, link) = (
+            folder.join("dotfiles/settings.yaml"),
+            folder.join("settings.yaml"),
+        );
+        fs::create_dir_all(target.parent().expect("No parent")).expect("Failed to create");
+        fs::write(&target, "a: 1\n").expect("Failed to write");
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).expect("Failed to chmod");
+        symlink(&target, &link).expect("Failed to link");
+        write_atomically(&link, "a: 2\n").expect("Failed to replace");
+        let metadata = fs::symlink_metadata(&link).expect("Failed to stat");
+        assert!(metadata.file_type().is_symlink());
+        assert_eq!(
+            fs::read_to_string(&target).expect("Failed to read"),
+            "a: 2\n"
+        );
+        let mode = fs::metadata(&target)
+            .expect("Failed to stat")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o640);
+        fs::remove_dir_all(folder).expect("Failed to clean up");
+    }
+}
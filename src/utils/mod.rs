@@ -1,4 +1,10 @@
+pub use atomic_writer::write_atomically;
 pub use tilde_expander::expand_tilde;
+pub use var_expander::{expand_path, expand_vars, UndefinedVariable};
+pub use xdg::application_dirs;
 
+mod atomic_writer;
 pub mod serde;
 mod tilde_expander;
+mod var_expander;
+mod xdg;
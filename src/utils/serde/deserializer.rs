@@ -1,6 +1,66 @@
+use std::io::{self, Error, ErrorKind};
+
 use serde::de::DeserializeOwned;
-use serde_json::Result;
 
-pub fn deserialize_from_bytes<D: DeserializeOwned>(bytes: &[u8]) -> Result<D> {
-    serde_json::from_slice(bytes)
+/// Format of a serialized document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// Detect the format from the content: JSON documents start with `{` or `[`,
+    /// anything else is treated as YAML
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') | Some(b'[') => Format::Json,
+            _ => Format::Yaml,
+        }
+    }
+}
+
+/// Deserialize a JSON or YAML document, detecting its format from the content
+pub fn deserialize_from_bytes<D: DeserializeOwned>(bytes: &[u8]) -> io::Result<D> {
+    match Format::detect(bytes) {
+        Format::Json => {
+            serde_json::from_slice(bytes).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+        }
+        Format::Yaml => {
+            serde_yaml::from_slice(bytes).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod deserializer_test {
+    use std::collections::HashMap;
+
+    use crate::utils::serde::deserializer::{deserialize_from_bytes, Format};
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(b"  \n{\"a\": 1}"), Format::Json);
+        assert_eq!(Format::detect(b"[1]"), Format::Json);
+        assert_eq!(Format::detect(b"a: 1"), Format::Yaml);
+        assert_eq!(Format::detect(b"---\na: 1"), Format::Yaml);
+    }
+
+    #[test]
+    fn test_deserialize_json() {
+        let res: HashMap<String, i32> = deserialize_from_bytes(br#"{"a": 1}"#).expect("Failed");
+        assert_eq!(res.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn test_deserialize_yaml() {
+        let res: HashMap<String, i32> = deserialize_from_bytes(b"a: 1\nb: 2").expect("Failed");
+        assert_eq!(res.get("b"), Some(&2));
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let res: std::io::Result<HashMap<String, i32>> = deserialize_from_bytes(b"{a: 1");
+        assert!(res.is_err());
+    }
 }
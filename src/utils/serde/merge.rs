@@ -0,0 +1,48 @@
+use serde_json::Value;
+
+/// Merge `layer` on top of `base`: objects are merged key by key, anything else is replaced
+pub fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+#[cfg(test)]
+mod merge_test {
+    use serde_json::json;
+
+    use crate::utils::serde::merge::merge;
+
+    #[test]
+    fn test_merge_objects() {
+        let mut base = json!({"internal": {"paths": ["/Applications"], "prefNames": {"a": "A"}}});
+        merge(&mut base, json!({"internal": {"prefNames": {"b": "B"}}}));
+        let expected =
+            json!({"internal": {"paths": ["/Applications"], "prefNames": {"a": "A", "b": "B"}}});
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn test_merge_replaces_arrays() {
+        let mut base = json!({"value": ["/Applications"]});
+        merge(&mut base, json!({"value": ["/opt"]}));
+        assert_eq!(base, json!({"value": ["/opt"]}));
+    }
+
+    #[test]
+    fn test_merge_replaces_scalars() {
+        let mut base = json!({"displayName": "Paths"});
+        merge(&mut base, json!({"displayName": null}));
+        assert_eq!(base, json!({"displayName": null}));
+    }
+}
@@ -1,5 +1,7 @@
-pub use deserializer::deserialize_from_bytes;
-pub use serializer::serialize_to_string;
+pub use deserializer::{deserialize_from_bytes, Format};
+pub use merge::merge;
+pub use serializer::{ensure_round_trip, serialize_as, serialize_to_string};
 
 mod deserializer;
+mod merge;
 mod serializer;
@@ -1,7 +1,39 @@
+use std::io::{self, Error, ErrorKind};
+
 use serde::Serialize;
 use serde_json::Result;
 
+use crate::utils::serde::deserializer::Format;
+
 /// Serialize object to [size;bytes] format
 pub fn serialize_to_string<S: Serialize>(obj: &S) -> Result<String> {
     serde_json::to_string(obj)
 }
+
+/// Serialize a document in the given format, ending with a newline
+pub fn serialize_as<S: Serialize>(format: Format, obj: &S) -> io::Result<String> {
+    let content = match format {
+        Format::Json => serde_json::to_string_pretty(obj).map(|content| content + "\n"),
+        Format::Yaml => {
+            return serde_yaml::to_string(obj)
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+        }
+    };
+    content.map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Fail unless the content, parsed into the document, reads the same once the document
+/// is serialized again, so rewriting it loses no comments or layout
+pub fn ensure_round_trip<S: Serialize>(format: Format, content: &[u8], obj: &S) -> io::Result<()> {
+    let normalize = |content: &str| content.trim_start_matches("---").trim().to_owned();
+    let rewritten = serialize_as(format, obj)?;
+    if normalize(&String::from_utf8_lossy(content)) == normalize(&rewritten) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "its comments or layout would be lost in rewriting it, \
+             edit it by hand or pass --force",
+        ))
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable referenced by a setting, but not defined
+#[derive(Debug, PartialEq)]
+pub struct UndefinedVariable(pub String);
+
+impl fmt::Display for UndefinedVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${} is not defined", self.0)
+    }
+}
+
+/// Expand `$VAR`, `${VAR}` and `${VAR:-default}`, then a leading tilde.
+/// Paths that are not valid UTF-8 are kept as they are.
+pub fn expand_path<P: AsRef<Path>>(path: P) -> Result<PathBuf, UndefinedVariable> {
+    let expanded = shellexpand::path::env(path.as_ref())
+        .map_err(|error| UndefinedVariable(error.var_name.to_string_lossy().into_owned()))?;
+    Ok(shellexpand::path::tilde(&expanded).into_owned())
+}
+
+/// Expand `$VAR`, `${VAR}` and `${VAR:-default}`, leaving a leading tilde to the caller
+pub fn expand_vars(text: &str) -> Result<String, UndefinedVariable> {
+    shellexpand::env(text)
+        .map(|expanded| expanded.into_owned())
+        .map_err(|error| UndefinedVariable(error.var_name))
+}
+
+#[cfg(test)]
+mod var_expander_test {
+    use std::path::PathBuf;
+
+    use crate::utils::var_expander::{expand_path, expand_vars, UndefinedVariable};
+
+    #[test]
+    fn test_expand_path() {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let expanded = expand_path("$HOME/Applications").expect("Failed to expand");
+        assert_eq!(expanded, PathBuf::from(format!("{}/Applications", home)));
+        let expanded = expand_path("~/Applications").expect("Failed to expand");
+        assert_eq!(expanded, PathBuf::from(format!("{}/Applications", home)));
+    }
+
+    #[test]
+    fn test_expand_path_default() {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let expanded = expand_path("${VAR_EXPANDER_UNSET:-~/.local/share}/applications")
+            .expect("Failed to expand");
+        let expected = format!("{}/.local/share/applications", home);
+        assert_eq!(expanded, PathBuf::from(expected));
+    }
+
+    #[test]
+    fn test_expand_path_undefined() {
+        let error = expand_path("$VAR_EXPANDER_UNSET/Applications").err();
+        assert_eq!(
+            error,
+            Some(UndefinedVariable("VAR_EXPANDER_UNSET".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_expand_path_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/Applications/\xff.app"));
+        assert_eq!(expand_path(&path), Ok(path));
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        assert_eq!(
+            expand_vars("!~/${VAR_EXPANDER_UNSET:-Apps}"),
+            Ok("!~/Apps".to_owned())
+        );
+        assert!(expand_vars("!$VAR_EXPANDER_UNSET").is_err());
+    }
+}
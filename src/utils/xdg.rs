@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use crate::utils::expand_tilde;
+
+/// Folders of `.desktop` files, in decreasing precedence: `$XDG_DATA_HOME/applications`,
+/// then `applications` under each of `$XDG_DATA_DIRS`
+pub fn application_dirs() -> Vec<PathBuf> {
+    application_dirs_with(|name| std::env::var(name).ok())
+}
+
+fn application_dirs_with<F: Fn(&str) -> Option<String>>(var: F) -> Vec<PathBuf> {
+    // Unset or empty variables take their defaults, and relative folders are skipped
+    let var = |name: &str, default: &str| {
+        var(name)
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| default.to_owned())
+    };
+    let data_home = var("XDG_DATA_HOME", "~/.local/share");
+    let data_dirs = var("XDG_DATA_DIRS", "/usr/local/share:/usr/share");
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in std::iter::once(data_home.as_str()).chain(data_dirs.split(':')) {
+        let dir = expand_tilde(dir);
+        if dir.is_absolute() && !dirs.contains(&dir.join("applications")) {
+            dirs.push(dir.join("applications"));
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod xdg_test {
+    use std::path::PathBuf;
+
+    use crate::utils::xdg::application_dirs_with;
+
+    #[test]
+    fn test_application_dirs() {
+        let dirs = application_dirs_with(|name| match name {
+            "XDG_DATA_HOME" => Some("/home/me/.data".to_owned()),
+            "XDG_DATA_DIRS" => Some("/opt/share:relative:/usr/share:/opt/share".to_owned()),
+            _ => None,
+        });
+        let expected = vec![
+            PathBuf::from("/home/me/.data/applications"),
+            PathBuf::from("/opt/share/applications"),
+            PathBuf::from("/usr/share/applications"),
+        ];
+        assert_eq!(dirs, expected);
+    }
+
+    #[test]
+    fn test_application_dirs_defaults() {
+        let dirs = application_dirs_with(|_| Some(String::new()));
+        assert_eq!(dirs.len(), 3);
+        assert!(dirs[0].ends_with(".local/share/applications"));
+        assert_eq!(dirs[2], PathBuf::from("/usr/share/applications"));
+    }
+}